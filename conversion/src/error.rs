@@ -0,0 +1,120 @@
+use std::error;
+use std::fmt;
+use std::num::{ParseFloatError, ParseIntError};
+
+/// Errors produced while converting into one of the crate's types.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConversionError {
+    /// The input did not have the shape `expected` describes.
+    Malformed {
+        expected: &'static str,
+        input: String,
+    },
+    /// An integer field could not be parsed.
+    Int(ParseIntError),
+    /// A floating point field could not be parsed.
+    Float(ParseFloatError),
+    /// The value was well formed but broke a type's invariant.
+    Invalid(ValidationError),
+}
+
+impl ConversionError {
+    pub(crate) fn malformed(expected: &'static str, input: &str) -> Self {
+        ConversionError::Malformed {
+            expected,
+            input: input.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConversionError::Malformed { expected, input } => {
+                write!(f, "expected {}, found {:?}", expected, input)
+            }
+            ConversionError::Int(..) => write!(f, "invalid integer"),
+            ConversionError::Float(..) => write!(f, "invalid floating point number"),
+            ConversionError::Invalid(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for ConversionError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ConversionError::Int(e) => Some(e),
+            ConversionError::Float(e) => Some(e),
+            ConversionError::Invalid(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseIntError> for ConversionError {
+    fn from(err: ParseIntError) -> ConversionError {
+        ConversionError::Int(err)
+    }
+}
+
+impl From<ParseFloatError> for ConversionError {
+    fn from(err: ParseFloatError) -> ConversionError {
+        ConversionError::Float(err)
+    }
+}
+
+impl From<ValidationError> for ConversionError {
+    fn from(err: ValidationError) -> ConversionError {
+        ConversionError::Invalid(err)
+    }
+}
+
+/// The rule a validated newtype enforces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Invariant {
+    Even,
+    Positive,
+    Percentage,
+    Port,
+    NonEmpty,
+    /// The result of an operation overflowed the named primitive.
+    Fits(&'static str),
+}
+
+impl fmt::Display for Invariant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Invariant::Even => write!(f, "must be even"),
+            Invariant::Positive => write!(f, "must be greater than zero"),
+            Invariant::Percentage => write!(f, "must be between 0 and 100"),
+            Invariant::Port => write!(f, "must be a port between 1 and 65535"),
+            Invariant::NonEmpty => write!(f, "must not be empty"),
+            Invariant::Fits(primitive) => write!(f, "must fit in {}", primitive),
+        }
+    }
+}
+
+/// The error shared by every validated newtype: which invariant failed, and
+/// the offending value as it would be printed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pub invariant: Invariant,
+    pub value: String,
+}
+
+impl ValidationError {
+    pub(crate) fn new(invariant: Invariant, value: impl fmt::Debug) -> Self {
+        ValidationError {
+            invariant,
+            value: format!("{:?}", value),
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.value, self.invariant)
+    }
+}
+
+impl error::Error for ValidationError {}
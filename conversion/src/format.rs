@@ -0,0 +1,209 @@
+// The formatting examples from `helloworld`, extended with parsers for their
+// `Display` output.
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use crate::ConversionError;
+
+/// A named location. Coordinates are printed with three decimals, so only
+/// values with at most three decimals survive a `to_string`/`parse` round trip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct City {
+    pub name: String,
+    pub lat: f32,
+    pub lon: f32,
+}
+
+impl Display for City {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let lat_c = if self.lat >= 0.0 { 'N' } else { 'S' };
+        let lon_c = if self.lon >= 0.0 { 'E' } else { 'W' };
+
+        write!(
+            f,
+            "{:<20}: {:.3}{} {:.3}{}",
+            self.name,
+            self.lat.abs(),
+            lat_c,
+            self.lon.abs(),
+            lon_c
+        )
+    }
+}
+
+// parses a coordinate such as `53.234N`, turning the hemisphere into a sign.
+fn parse_coordinate(s: &str, positive: char, negative: char) -> Result<f32, ConversionError> {
+    let expected = "a coordinate such as \"53.234N\"";
+    let hemisphere = s
+        .chars()
+        .last()
+        .ok_or_else(|| ConversionError::malformed(expected, s))?;
+    let value: f32 = s[..s.len() - hemisphere.len_utf8()].parse()?;
+    if hemisphere == positive {
+        Ok(value)
+    } else if hemisphere == negative {
+        Ok(-value)
+    } else {
+        Err(ConversionError::malformed(expected, s))
+    }
+}
+
+impl FromStr for City {
+    type Err = ConversionError;
+
+    /// Parses the `Display` form, e.g. `"Dublin              : 53.234N 6.253W"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expected = "\"<name>: <lat>N|S <lon>E|W\"";
+        let (name, coordinates) = s
+            .rsplit_once(": ")
+            .ok_or_else(|| ConversionError::malformed(expected, s))?;
+        let mut parts = coordinates.split_whitespace();
+        let (Some(lat), Some(lon), None) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(ConversionError::malformed(expected, s));
+        };
+
+        Ok(City {
+            name: name.trim_end().to_string(),
+            lat: parse_coordinate(lat, 'N', 'S')?,
+            lon: parse_coordinate(lon, 'E', 'W')?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Display for Color {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "RGB ({r}, {g}, {b}) 0x{r:02X}{g:02X}{b:02X}",
+            r = self.r,
+            g = self.g,
+            b = self.b
+        )
+    }
+}
+
+impl FromStr for Color {
+    type Err = ConversionError;
+
+    /// Parses the `Display` form, e.g. `"RGB (128, 255, 90) 0x80FF5A"`.
+    /// The decimal and hexadecimal parts must agree.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expected = "\"RGB (<r>, <g>, <b>) 0x<RRGGBB>\"";
+        let malformed = || ConversionError::malformed(expected, s);
+
+        let (decimal, hex) = s
+            .trim()
+            .strip_prefix("RGB (")
+            .and_then(|rest| rest.split_once(") 0x"))
+            .ok_or_else(malformed)?;
+
+        let mut channels = decimal.split(", ");
+        let (Some(r), Some(g), Some(b), None) = (
+            channels.next(),
+            channels.next(),
+            channels.next(),
+            channels.next(),
+        ) else {
+            return Err(malformed());
+        };
+        let color = Color {
+            r: r.parse()?,
+            g: g.parse()?,
+            b: b.parse()?,
+        };
+
+        if hex.len() != 6 || u32::from_str_radix(hex, 16)? != color.to_rgb() {
+            return Err(malformed());
+        }
+        Ok(color)
+    }
+}
+
+impl Color {
+    fn to_rgb(self) -> u32 {
+        (self.r as u32) << 16 | (self.g as u32) << 8 | self.b as u32
+    }
+}
+
+/// A complex number printed as `"3.3 + j7.2"`. `f64`'s `Display` is lossless,
+/// so every non-NaN value round trips.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Complex {
+    pub real: f64,
+    pub imag: f64,
+}
+
+impl Display for Complex {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{} + j{}", self.real, self.imag)
+    }
+}
+
+impl FromStr for Complex {
+    type Err = ConversionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (real, imag) = s
+            .trim()
+            .split_once(" + j")
+            .ok_or_else(|| ConversionError::malformed("\"<real> + j<imag>\"", s))?;
+        Ok(Complex {
+            real: real.parse()?,
+            imag: imag.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn city_prints_hemispheres_without_sign() {
+        let dublin = City {
+            name: "Dublin".to_string(),
+            lat: 53.234,
+            lon: -6.253,
+        };
+        assert_eq!(dublin.to_string(), "Dublin              : 53.234N 6.253W");
+        assert_eq!(dublin.to_string().parse(), Ok(dublin));
+    }
+
+    #[test]
+    fn city_rejects_unknown_hemisphere() {
+        assert!("Oslo: 59.950X 10.750E".parse::<City>().is_err());
+        assert!("Oslo 59.950N 10.750E".parse::<City>().is_err());
+    }
+
+    #[test]
+    fn color_hex_must_match_channels() {
+        assert_eq!(
+            "RGB (128, 255, 90) 0x80FF5A".parse(),
+            Ok(Color {
+                r: 128,
+                g: 255,
+                b: 90
+            })
+        );
+        assert!("RGB (128, 255, 90) 0x000000".parse::<Color>().is_err());
+        assert!("RGB (256, 0, 0) 0x000000".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn complex_with_negative_imaginary_part() {
+        let c = Complex {
+            real: 3.3,
+            imag: -7.2,
+        };
+        assert_eq!(c.to_string(), "3.3 + j-7.2");
+        assert_eq!(c.to_string().parse(), Ok(c));
+    }
+}
@@ -0,0 +1,13 @@
+// Conversion library
+//
+// The types demonstrated in `main.rs` live here so they can be shared and tested.
+// Every type implements both `fmt::Display` and `str::FromStr`, and the two are
+// inverses of each other: `x.to_string().parse() == Ok(x)`.
+
+pub mod error;
+pub mod format;
+pub mod number;
+pub mod shape;
+pub mod validated;
+
+pub use error::{ConversionError, Invariant, ValidationError};
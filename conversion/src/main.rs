@@ -117,22 +117,15 @@ fn main() {
 // section 3: To and From String
 
 /* */
-use std::fmt;
-
-struct Circle {
-    radius: i32,
-}
-
-impl fmt::Display for Circle {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Circle of radius {}", self.radius)
-    }
-}
+use conversion::format::{City, Color, Complex};
+use conversion::shape::Circle;
+use conversion::validated::EvenNumber;
 
 fn main() {
     // converto to string
     let circle = Circle { radius: 7 };
-    println!("{}", circle.to_string());
+    let text = circle.to_string();
+    println!("{}", text);
     println!("{}", circle);
 
     // parsing a string
@@ -143,4 +136,21 @@ fn main() {
 
     let float: f64 = "3.14".parse().unwrap();
     println!("{}", float);
+
+    // `FromStr` is the inverse of `Display`
+    let circle: Circle = text.parse().unwrap();
+    println!("{:?}", circle);
+
+    println!("{:?}", "8".parse::<EvenNumber>());
+    match "5".parse::<EvenNumber>() {
+        Ok(num) => println!("value is {}", num),
+        Err(e) => println!("error: {}", e),
+    }
+
+    let city: City = "Dublin: 53.234N 6.253W".parse().unwrap();
+    println!("{}", city);
+    let color: Color = "RGB (0, 3, 254) 0x0003FE".parse().unwrap();
+    println!("{:?}", color);
+    let complex: Complex = "3.3 + j7.2".parse().unwrap();
+    println!("{:?}", complex);
 }
@@ -0,0 +1,43 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ConversionError;
+
+/// A plain wrapper around `i32`, built with `From`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Number {
+    pub value: i32,
+}
+
+impl From<i32> for Number {
+    fn from(item: i32) -> Self {
+        Number { value: item }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl FromStr for Number {
+    type Err = ConversionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Number::from(s.trim().parse::<i32>()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn number_reports_parse_errors() {
+        assert!(matches!(
+            "x".parse::<Number>(),
+            Err(ConversionError::Int(_))
+        ));
+    }
+}
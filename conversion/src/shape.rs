@@ -0,0 +1,50 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ConversionError;
+
+const CIRCLE_PREFIX: &str = "Circle of radius ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Circle {
+    pub radius: i32,
+}
+
+impl fmt::Display for Circle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", CIRCLE_PREFIX, self.radius)
+    }
+}
+
+impl FromStr for Circle {
+    type Err = ConversionError;
+
+    /// Parses the `Display` form, e.g. `"Circle of radius 7"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let radius = s
+            .trim()
+            .strip_prefix(CIRCLE_PREFIX)
+            .ok_or_else(|| ConversionError::malformed("\"Circle of radius <n>\"", s))?;
+        Ok(Circle {
+            radius: radius.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_circle() {
+        assert_eq!("Circle of radius 7".parse(), Ok(Circle { radius: 7 }));
+        assert!(matches!(
+            "Square of side 7".parse::<Circle>(),
+            Err(ConversionError::Malformed { .. })
+        ));
+        assert!(matches!(
+            "Circle of radius seven".parse::<Circle>(),
+            Err(ConversionError::Int(_))
+        ));
+    }
+}
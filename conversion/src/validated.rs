@@ -0,0 +1,556 @@
+// Validated newtypes
+//
+// Each type wraps a primitive and can only be built through `TryFrom`, which
+// checks the type's invariant. `From` converts back to the primitive. Where the
+// result of an operator always keeps the invariant it returns the type itself;
+// otherwise it returns a `Result`, like the `checked_*` method it delegates to.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+
+use crate::{ConversionError, Invariant, ValidationError};
+
+// `FromStr` for a newtype: parse the primitive, then validate it with `TryFrom`.
+macro_rules! from_str_via {
+    ($ty:ty, $primitive:ty) => {
+        impl FromStr for $ty {
+            type Err = ConversionError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok(<$ty>::try_from(s.trim().parse::<$primitive>()?)?)
+            }
+        }
+    };
+}
+
+// A binary operator returning the `Result` of the matching `checked_*` method.
+macro_rules! checked_op {
+    ($ty:ident, $op:ident, $method:ident, $rhs:ty, $checked:ident) => {
+        impl $op<$rhs> for $ty {
+            type Output = Result<$ty, ValidationError>;
+
+            fn $method(self, rhs: $rhs) -> Result<$ty, ValidationError> {
+                self.$checked(rhs)
+            }
+        }
+    };
+}
+
+// The exact result of an operation that overflowed `primitive`.
+fn overflow(primitive: &'static str, exact: impl fmt::Debug) -> ValidationError {
+    ValidationError::new(Invariant::Fits(primitive), exact)
+}
+
+// --------------------------------------------------------------------------------
+// EvenNumber
+
+/// An `i32` that is known to be even.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EvenNumber(i32);
+
+impl EvenNumber {
+    pub fn value(self) -> i32 {
+        self.0
+    }
+
+    /// Half of an even number is always a whole number.
+    pub fn half(self) -> i32 {
+        self.0 / 2
+    }
+
+    /// Sums, differences, negations and multiples of even numbers are even,
+    /// but may not fit in an `i32`; the error then carries the exact result.
+    pub fn checked_add(self, rhs: EvenNumber) -> Result<EvenNumber, ValidationError> {
+        self.0
+            .checked_add(rhs.0)
+            .map(EvenNumber)
+            .ok_or_else(|| overflow("i32", self.0 as i64 + rhs.0 as i64))
+    }
+
+    pub fn checked_sub(self, rhs: EvenNumber) -> Result<EvenNumber, ValidationError> {
+        self.0
+            .checked_sub(rhs.0)
+            .map(EvenNumber)
+            .ok_or_else(|| overflow("i32", self.0 as i64 - rhs.0 as i64))
+    }
+
+    /// Fails only for `i32::MIN`, which is even but has no positive counterpart.
+    pub fn checked_neg(self) -> Result<EvenNumber, ValidationError> {
+        self.0
+            .checked_neg()
+            .map(EvenNumber)
+            .ok_or_else(|| overflow("i32", -(self.0 as i64)))
+    }
+
+    pub fn checked_mul(self, rhs: i32) -> Result<EvenNumber, ValidationError> {
+        self.0
+            .checked_mul(rhs)
+            .map(EvenNumber)
+            .ok_or_else(|| overflow("i32", self.0 as i64 * rhs as i64))
+    }
+}
+
+impl TryFrom<i32> for EvenNumber {
+    type Error = ValidationError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        if value % 2 == 0 {
+            Ok(EvenNumber(value))
+        } else {
+            Err(ValidationError::new(Invariant::Even, value))
+        }
+    }
+}
+
+impl From<EvenNumber> for i32 {
+    fn from(num: EvenNumber) -> i32 {
+        num.0
+    }
+}
+
+impl From<EvenNumber> for i64 {
+    fn from(num: EvenNumber) -> i64 {
+        num.0 as i64
+    }
+}
+
+checked_op!(EvenNumber, Add, add, EvenNumber, checked_add);
+checked_op!(EvenNumber, Sub, sub, EvenNumber, checked_sub);
+checked_op!(EvenNumber, Mul, mul, i32, checked_mul);
+
+impl Neg for EvenNumber {
+    type Output = Result<EvenNumber, ValidationError>;
+
+    fn neg(self) -> Result<EvenNumber, ValidationError> {
+        self.checked_neg()
+    }
+}
+
+impl fmt::Display for EvenNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+from_str_via!(EvenNumber, i32);
+
+// --------------------------------------------------------------------------------
+// PositiveInt
+
+/// A `u32` strictly greater than zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PositiveInt(u32);
+
+impl PositiveInt {
+    pub fn value(self) -> u32 {
+        self.0
+    }
+
+    pub fn checked_add(self, rhs: PositiveInt) -> Result<PositiveInt, ValidationError> {
+        self.0
+            .checked_add(rhs.0)
+            .map(PositiveInt)
+            .ok_or_else(|| overflow("u32", self.0 as u64 + rhs.0 as u64))
+    }
+
+    pub fn checked_mul(self, rhs: PositiveInt) -> Result<PositiveInt, ValidationError> {
+        self.0
+            .checked_mul(rhs.0)
+            .map(PositiveInt)
+            .ok_or_else(|| overflow("u32", self.0 as u64 * rhs.0 as u64))
+    }
+
+    /// Subtraction only stays positive when `rhs` is smaller than `self`.
+    pub fn checked_sub(self, rhs: PositiveInt) -> Result<PositiveInt, ValidationError> {
+        if self.0 > rhs.0 {
+            Ok(PositiveInt(self.0 - rhs.0))
+        } else {
+            Err(ValidationError::new(
+                Invariant::Positive,
+                self.0 as i64 - rhs.0 as i64,
+            ))
+        }
+    }
+}
+
+impl TryFrom<u32> for PositiveInt {
+    type Error = ValidationError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        if value > 0 {
+            Ok(PositiveInt(value))
+        } else {
+            Err(ValidationError::new(Invariant::Positive, value))
+        }
+    }
+}
+
+impl TryFrom<i32> for PositiveInt {
+    type Error = ValidationError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        if value > 0 {
+            Ok(PositiveInt(value as u32))
+        } else {
+            Err(ValidationError::new(Invariant::Positive, value))
+        }
+    }
+}
+
+impl From<PositiveInt> for u32 {
+    fn from(num: PositiveInt) -> u32 {
+        num.0
+    }
+}
+
+impl From<PositiveInt> for u64 {
+    fn from(num: PositiveInt) -> u64 {
+        num.0 as u64
+    }
+}
+
+impl From<PositiveInt> for i64 {
+    fn from(num: PositiveInt) -> i64 {
+        num.0 as i64
+    }
+}
+
+checked_op!(PositiveInt, Add, add, PositiveInt, checked_add);
+checked_op!(PositiveInt, Sub, sub, PositiveInt, checked_sub);
+checked_op!(PositiveInt, Mul, mul, PositiveInt, checked_mul);
+
+impl fmt::Display for PositiveInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+from_str_via!(PositiveInt, u32);
+
+// --------------------------------------------------------------------------------
+// Percentage
+
+/// A value between 0 and 100 inclusive. `NaN` is rejected.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Percentage(f64);
+
+impl Percentage {
+    pub fn value(self) -> f64 {
+        self.0
+    }
+
+    /// The remaining share, `100 - self`.
+    pub fn complement(self) -> Percentage {
+        Percentage(100.0 - self.0)
+    }
+
+    /// Applies the percentage to `amount`.
+    pub fn of(self, amount: f64) -> f64 {
+        amount * self.0 / 100.0
+    }
+
+    /// Adding two percentages can exceed 100.
+    pub fn checked_add(self, rhs: Percentage) -> Result<Percentage, ValidationError> {
+        Percentage::try_from(self.0 + rhs.0)
+    }
+}
+
+impl TryFrom<f64> for Percentage {
+    type Error = ValidationError;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        // written this way round so that `NaN` fails the check.
+        if (0.0..=100.0).contains(&value) {
+            Ok(Percentage(value))
+        } else {
+            Err(ValidationError::new(Invariant::Percentage, value))
+        }
+    }
+}
+
+impl TryFrom<i32> for Percentage {
+    type Error = ValidationError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        if (0..=100).contains(&value) {
+            Ok(Percentage(value as f64))
+        } else {
+            Err(ValidationError::new(Invariant::Percentage, value))
+        }
+    }
+}
+
+impl From<Percentage> for f64 {
+    fn from(p: Percentage) -> f64 {
+        p.0
+    }
+}
+
+// a percentage of a percentage stays within 0..=100.
+impl Mul for Percentage {
+    type Output = Percentage;
+
+    fn mul(self, rhs: Percentage) -> Percentage {
+        Percentage(self.0 * rhs.0 / 100.0)
+    }
+}
+
+impl fmt::Display for Percentage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}%", self.0)
+    }
+}
+
+impl FromStr for Percentage {
+    type Err = ConversionError;
+
+    /// Accepts `"42.5"` as well as the `Display` form `"42.5%"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let number = s.strip_suffix('%').unwrap_or(s);
+        Ok(Percentage::try_from(number.trim_end().parse::<f64>()?)?)
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Port
+
+/// A TCP/UDP port number. Port 0 is reserved and rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Port(u16);
+
+impl Port {
+    pub fn value(self) -> u16 {
+        self.0
+    }
+
+    /// Ports below 1024 usually need elevated privileges to bind.
+    pub fn is_privileged(self) -> bool {
+        self.0 < 1024
+    }
+}
+
+impl TryFrom<u16> for Port {
+    type Error = ValidationError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        if value != 0 {
+            Ok(Port(value))
+        } else {
+            Err(ValidationError::new(Invariant::Port, value))
+        }
+    }
+}
+
+impl TryFrom<i32> for Port {
+    type Error = ValidationError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match u16::try_from(value) {
+            Ok(port) if port != 0 => Ok(Port(port)),
+            _ => Err(ValidationError::new(Invariant::Port, value)),
+        }
+    }
+}
+
+impl TryFrom<u32> for Port {
+    type Error = ValidationError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match u16::try_from(value) {
+            Ok(port) if port != 0 => Ok(Port(port)),
+            _ => Err(ValidationError::new(Invariant::Port, value)),
+        }
+    }
+}
+
+impl From<Port> for u16 {
+    fn from(port: Port) -> u16 {
+        port.0
+    }
+}
+
+impl From<Port> for u32 {
+    fn from(port: Port) -> u32 {
+        port.0 as u32
+    }
+}
+
+impl fmt::Display for Port {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// parse as `u32` so that `"70000"` reports the port invariant, not an overflow.
+from_str_via!(Port, u32);
+
+// --------------------------------------------------------------------------------
+// NonEmptyString
+
+/// A `String` with at least one character.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NonEmptyString(String);
+
+impl NonEmptyString {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn first_char(&self) -> char {
+        // cannot fail: the string is never empty.
+        self.0.chars().next().unwrap()
+    }
+}
+
+impl TryFrom<String> for NonEmptyString {
+    type Error = ValidationError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value.is_empty() {
+            Err(ValidationError::new(Invariant::NonEmpty, value))
+        } else {
+            Ok(NonEmptyString(value))
+        }
+    }
+}
+
+impl TryFrom<&str> for NonEmptyString {
+    type Error = ValidationError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        NonEmptyString::try_from(value.to_string())
+    }
+}
+
+impl From<NonEmptyString> for String {
+    fn from(s: NonEmptyString) -> String {
+        s.0
+    }
+}
+
+impl AsRef<str> for NonEmptyString {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// appending to a non-empty string keeps it non-empty.
+impl Add<&str> for NonEmptyString {
+    type Output = NonEmptyString;
+
+    fn add(self, rhs: &str) -> NonEmptyString {
+        NonEmptyString(self.0 + rhs)
+    }
+}
+
+impl fmt::Display for NonEmptyString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for NonEmptyString {
+    type Err = ConversionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(NonEmptyString::try_from(s)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_name_the_invariant_and_value() {
+        let err = EvenNumber::try_from(5).unwrap_err();
+        assert_eq!(err.invariant, Invariant::Even);
+        assert_eq!(err.to_string(), "5 must be even");
+
+        let err = NonEmptyString::try_from("").unwrap_err();
+        assert_eq!(err.to_string(), "\"\" must not be empty");
+
+        assert_eq!(
+            "70000".parse::<Port>(),
+            Err(ConversionError::Invalid(ValidationError::new(
+                Invariant::Port,
+                70000u32
+            )))
+        );
+    }
+
+    #[test]
+    fn arithmetic_preserves_invariants() {
+        let a = EvenNumber::try_from(4).unwrap();
+        let b = EvenNumber::try_from(6).unwrap();
+        assert_eq!((a + b).map(i32::from), Ok(10));
+        assert_eq!((a - b).map(i32::from), Ok(-2));
+        assert_eq!((-a).map(i32::from), Ok(-4));
+        assert_eq!((a * 3).map(EvenNumber::half), Ok(6));
+        assert_eq!(a + b, a.checked_add(b));
+
+        let one = PositiveInt::try_from(1u32).unwrap();
+        let two = (one + one).unwrap();
+        assert_eq!((two * two).map(u32::from), Ok(4));
+        assert_eq!(two - one, Ok(one));
+        assert_eq!((one - two).unwrap_err().value, "-1");
+
+        let half = Percentage::try_from(50).unwrap();
+        assert_eq!(f64::from(half * half), 25.0);
+        assert!(half.checked_add(half.complement()).is_ok());
+        assert!(half.checked_add(Percentage::try_from(51).unwrap()).is_err());
+    }
+
+    #[test]
+    fn arithmetic_fails_at_the_boundaries() {
+        let min = EvenNumber::try_from(i32::MIN).unwrap();
+        let max = EvenNumber::try_from(i32::MAX - 1).unwrap();
+        let two = EvenNumber::try_from(2).unwrap();
+        let err = min.checked_neg().unwrap_err();
+        assert_eq!(err.invariant, Invariant::Fits("i32"));
+        assert_eq!(err.to_string(), "2147483648 must fit in i32");
+        assert_eq!(max.checked_add(two).unwrap_err().value, "2147483648");
+        assert!(min.checked_sub(two).is_err());
+        assert!(max.checked_mul(2).is_err());
+        assert_eq!(max.checked_neg().map(i32::from), Ok(-(i32::MAX - 1)));
+        assert_eq!(min.checked_add(max).map(i32::from), Ok(-2));
+        assert!((-min).is_err());
+        assert!((max + two).is_err());
+
+        let max = PositiveInt::try_from(u32::MAX).unwrap();
+        let one = PositiveInt::try_from(1u32).unwrap();
+        let two = PositiveInt::try_from(2u32).unwrap();
+        assert_eq!(max.checked_add(one).unwrap_err().value, "4294967296");
+        assert_eq!(
+            max.checked_mul(two).unwrap_err().to_string(),
+            "8589934590 must fit in u32"
+        );
+        assert_eq!(max.checked_mul(one), Ok(max));
+        assert!((max + one).is_err());
+    }
+
+    #[test]
+    fn percentage_rejects_nan() {
+        assert!(Percentage::try_from(f64::NAN).is_err());
+        assert_eq!("12.5%".parse::<Percentage>().map(f64::from), Ok(12.5));
+    }
+
+    #[test]
+    fn port_range() {
+        assert!(Port::try_from(0u16).is_err());
+        assert!(Port::try_from(-1).is_err());
+        assert!(Port::try_from(80).unwrap().is_privileged());
+        assert_eq!(u16::from(Port::try_from(65535u32).unwrap()), u16::MAX);
+    }
+
+    #[test]
+    fn non_empty_string_concatenation() {
+        let s = NonEmptyString::try_from("a").unwrap() + "bc";
+        assert_eq!(s.as_str(), "abc");
+        assert_eq!(s.first_char(), 'a');
+        assert_eq!(String::from(s), "abc");
+    }
+}
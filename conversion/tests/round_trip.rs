@@ -0,0 +1,136 @@
+// Property tests: for every type, `parse(x.to_string()) == x` over many
+// generated values.
+
+use std::convert::TryFrom;
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+
+use conversion::format::{City, Color, Complex};
+use conversion::number::Number;
+use conversion::shape::Circle;
+use conversion::validated::{EvenNumber, NonEmptyString, Percentage, Port, PositiveInt};
+
+const CASES: usize = 1000;
+
+// a small xorshift generator, so the cases are reproducible without extra crates.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn i32(&mut self) -> i32 {
+        self.next() as i32
+    }
+
+    fn u8(&mut self) -> u8 {
+        self.next() as u8
+    }
+
+    fn range(&mut self, lo: i32, hi: i32) -> i32 {
+        lo + (self.next() % (hi - lo + 1) as u64) as i32
+    }
+
+    fn f64(&mut self) -> f64 {
+        let value = f64::from_bits(self.next());
+        if value.is_nan() {
+            0.0
+        } else {
+            value
+        }
+    }
+}
+
+fn check_round_trip<T, F>(mut generate: F)
+where
+    T: Display + FromStr + PartialEq + Debug,
+    T::Err: Debug,
+    F: FnMut(&mut Rng) -> T,
+{
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..CASES {
+        let value = generate(&mut rng);
+        let text = value.to_string();
+        let parsed = text
+            .parse::<T>()
+            .unwrap_or_else(|e| panic!("failed to parse {:?}: {:?}", text, e));
+        assert_eq!(parsed, value, "round trip through {:?}", text);
+    }
+}
+
+#[test]
+fn circle_round_trips() {
+    check_round_trip(|rng| Circle { radius: rng.i32() });
+}
+
+#[test]
+fn number_round_trips() {
+    check_round_trip(|rng| Number::from(rng.i32()));
+}
+
+#[test]
+fn even_number_round_trips() {
+    check_round_trip(|rng| EvenNumber::try_from(rng.i32() & !1).unwrap());
+}
+
+#[test]
+fn city_round_trips() {
+    let names = [
+        "Dublin",
+        "Oslo",
+        "Vancouver",
+        "Rio de Janeiro",
+        "A very long city name",
+    ];
+    check_round_trip(|rng| City {
+        name: names[rng.range(0, names.len() as i32 - 1) as usize].to_string(),
+        // only three decimals are printed, so generate values on that grid.
+        lat: rng.range(-90_000, 90_000) as f32 / 1000.0,
+        lon: rng.range(-180_000, 180_000) as f32 / 1000.0,
+    });
+}
+
+#[test]
+fn color_round_trips() {
+    check_round_trip(|rng| Color {
+        r: rng.u8(),
+        g: rng.u8(),
+        b: rng.u8(),
+    });
+}
+
+#[test]
+fn complex_round_trips() {
+    check_round_trip(|rng| Complex {
+        real: rng.f64(),
+        imag: rng.f64(),
+    });
+}
+
+#[test]
+fn positive_int_round_trips() {
+    check_round_trip(|rng| PositiveInt::try_from(rng.next() as u32 | 1).unwrap());
+}
+
+#[test]
+fn percentage_round_trips() {
+    check_round_trip(|rng| Percentage::try_from((rng.next() % 10_001) as f64 / 100.0).unwrap());
+}
+
+#[test]
+fn port_round_trips() {
+    check_round_trip(|rng| Port::try_from(rng.next() as u16 | 1).unwrap());
+}
+
+#[test]
+fn non_empty_string_round_trips() {
+    let words = ["a", "hello", "hello world", "ünïcødé", " padded "];
+    check_round_trip(|rng| {
+        let word = words[rng.range(0, words.len() as i32 - 1) as usize];
+        NonEmptyString::try_from(word).unwrap()
+    });
+}
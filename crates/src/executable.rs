@@ -1,4 +1,4 @@
-// $ rustc executable.rs --extern rary=library.rlib
+// $ cargo run -p rary --bin executable
 
 fn main() {
     rary::public_function();
@@ -6,4 +6,6 @@ fn main() {
     // rary::private_function(); // ERROR...
 
     rary::indirect_access();
-}
\ No newline at end of file
+
+    println!("rary version {}", rary::VERSION);
+}
@@ -1,15 +1,68 @@
-// $ rustc --crate-type=lib rary.rs
+//! A library crate, built by cargo instead of `rustc --crate-type=lib rary.rs`.
+//!
+//! The API lives in versioned modules. The crate root re-exports [`v1`], so
+//! existing callers of `rary::public_function()` keep working.
+//!
+//! ```
+//! rary::public_function();
+//! rary::indirect_access();
+//! assert_eq!(rary::v2::public_function(), "called rary's `public_function()`");
+//! ```
 
-pub fn public_function() {
-    println!("called rary's `public_function()`");
-}
+/// The version of the crate, from `Cargo.toml`.
+///
+/// ```
+/// assert_eq!(rary::VERSION, "0.1.0");
+/// ```
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub use v1::{indirect_access, public_function};
 
-fn private_function() {
-    println!("called rary's `private_function()`");
+/// The original API, which prints its messages.
+pub mod v1 {
+    /// Prints a message.
+    ///
+    /// ```
+    /// rary::v1::public_function();
+    /// ```
+    pub fn public_function() {
+        println!("{}", super::v2::public_function());
+    }
+
+    /// Prints a message after calling a private function.
+    ///
+    /// ```
+    /// rary::v1::indirect_access();
+    /// ```
+    pub fn indirect_access() {
+        println!("{}", super::v2::indirect_access());
+    }
 }
 
-pub fn indirect_access() {
-    print!("called rary's `indirect_access()`, that\n> ");
+/// Returns the messages instead of printing them, so callers can decide.
+pub mod v2 {
+    /// ```
+    /// assert_eq!(
+    ///     rary::v2::public_function(),
+    ///     "called rary's `public_function()`"
+    /// );
+    /// ```
+    pub fn public_function() -> String {
+        "called rary's `public_function()`".to_string()
+    }
+
+    fn private_function() -> String {
+        "called rary's `private_function()`".to_string()
+    }
 
-    private_function();
+    /// ```
+    /// let message = rary::v2::indirect_access();
+    /// assert!(message.ends_with("> called rary's `private_function()`"));
+    /// ```
+    pub fn indirect_access() -> String {
+        format!(
+            "called rary's `indirect_access()`, that\n> {}",
+            private_function()
+        )
+    }
 }
@@ -0,0 +1,467 @@
+// Web events
+//
+// An event bus for the `WebEvent` enum: handlers are registered per variant,
+// events are queued and delivered in order, and delivered events can be
+// recorded to a line-based log which can be parsed back and replayed.
+//
+// Log format, one event per line:
+//
+//     load
+//     unload
+//     key x
+//     paste some text
+//     click 20 80
+//
+// Backslash, newline, carriage return and tab in key and paste payloads are
+// written as `\\`, `\n`, `\r` and `\t`. Blank lines and lines starting with `#`
+// are ignored when parsing.
+
+use std::collections::VecDeque;
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebEvent {
+    PageLoad,
+    PageUnload,
+    KeyPress(char),
+    Paste(String),
+    Click { x: i64, y: i64 },
+}
+
+/// The variant of a `WebEvent`, without its data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    PageLoad,
+    PageUnload,
+    KeyPress,
+    Paste,
+    Click,
+}
+
+impl WebEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            WebEvent::PageLoad => EventKind::PageLoad,
+            WebEvent::PageUnload => EventKind::PageUnload,
+            WebEvent::KeyPress(_) => EventKind::KeyPress,
+            WebEvent::Paste(_) => EventKind::Paste,
+            WebEvent::Click { .. } => EventKind::Click,
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn unescape(s: &str) -> Option<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => out.push('\\'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+/// Writes the event in the log format.
+impl fmt::Display for WebEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WebEvent::PageLoad => write!(f, "load"),
+            WebEvent::PageUnload => write!(f, "unload"),
+            WebEvent::KeyPress(c) => write!(f, "key {}", escape(&c.to_string())),
+            WebEvent::Paste(s) => write!(f, "paste {}", escape(s)),
+            WebEvent::Click { x, y } => write!(f, "click {} {}", x, y),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseEventError {
+    /// 1-based line in the log, or 0 when a single event was parsed.
+    pub line: usize,
+    pub input: String,
+}
+
+impl fmt::Display for ParseEventError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "line {}: ", self.line)?;
+        }
+        write!(f, "invalid event {:?}", self.input)
+    }
+}
+
+impl error::Error for ParseEventError {}
+
+impl FromStr for WebEvent {
+    type Err = ParseEventError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseEventError {
+            line: 0,
+            input: s.to_string(),
+        };
+        // the payload is not trimmed: a key press may be a space.
+        let (name, payload) = match s.split_once(' ') {
+            Some((name, payload)) => (name, Some(payload)),
+            None => (s, None),
+        };
+
+        let event = match (name, payload) {
+            ("load", None) => WebEvent::PageLoad,
+            ("unload", None) => WebEvent::PageUnload,
+            ("key", Some(payload)) => {
+                let text = unescape(payload).ok_or_else(error)?;
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => WebEvent::KeyPress(c),
+                    _ => return Err(error()),
+                }
+            }
+            ("paste", Some(payload)) => WebEvent::Paste(unescape(payload).ok_or_else(error)?),
+            ("click", Some(payload)) => {
+                let mut coords = payload.split(' ').map(i64::from_str);
+                match (coords.next(), coords.next(), coords.next()) {
+                    (Some(Ok(x)), Some(Ok(y)), None) => WebEvent::Click { x, y },
+                    _ => return Err(error()),
+                }
+            }
+            _ => return Err(error()),
+        };
+        Ok(event)
+    }
+}
+
+/// Writes events in the log format, one per line.
+pub fn to_log(events: &[WebEvent]) -> String {
+    events.iter().map(|e| format!("{}\n", e)).collect()
+}
+
+/// Parses a log written by `to_log`, reporting the first bad line.
+pub fn parse_log(log: &str) -> Result<Vec<WebEvent>, ParseEventError> {
+    log.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(index, line)| {
+            line.parse().map_err(|e: ParseEventError| ParseEventError {
+                line: index + 1,
+                ..e
+            })
+        })
+        .collect()
+}
+
+/// A rectangle in page coordinates, `x..x + width` by `y..y + height`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
+}
+
+impl Region {
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        // in `i128` so that regions reaching the edges of `i64` don't overflow.
+        let within =
+            |p: i64, start: i64, len: i64| p >= start && (p as i128 - start as i128) < len as i128;
+        within(x, self.x, self.width) && within(y, self.y, self.height)
+    }
+
+    /// A filter accepting only clicks inside this region.
+    pub fn clicks(self) -> impl Fn(&WebEvent) -> bool {
+        move |event| match *event {
+            WebEvent::Click { x, y } => self.contains(x, y),
+            _ => false,
+        }
+    }
+}
+
+/// Returned by `EventBus::on`, used to remove the handler again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HandlerId(usize);
+
+type Handler<'a> = Box<dyn FnMut(&WebEvent) + 'a>;
+type Filter<'a> = Box<dyn Fn(&WebEvent) -> bool + 'a>;
+
+struct Registration<'a> {
+    id: HandlerId,
+    kind: EventKind,
+    filter: Option<Filter<'a>>,
+    handler: Handler<'a>,
+}
+
+/// Queues events and delivers them, in the order they were pushed, to the
+/// handlers registered for their variant. Handlers for the same variant run
+/// in registration order.
+#[derive(Default)]
+pub struct EventBus<'a> {
+    registrations: Vec<Registration<'a>>,
+    queue: VecDeque<WebEvent>,
+    recording: Option<Vec<WebEvent>>,
+    next_id: usize,
+}
+
+impl<'a> EventBus<'a> {
+    pub fn new() -> EventBus<'a> {
+        EventBus::default()
+    }
+
+    pub fn on<F>(&mut self, kind: EventKind, handler: F) -> HandlerId
+    where
+        F: FnMut(&WebEvent) + 'a,
+    {
+        self.register(kind, None, Box::new(handler))
+    }
+
+    /// Registers a handler that only sees events accepted by `filter`.
+    pub fn on_filtered<P, F>(&mut self, kind: EventKind, filter: P, handler: F) -> HandlerId
+    where
+        P: Fn(&WebEvent) -> bool + 'a,
+        F: FnMut(&WebEvent) + 'a,
+    {
+        self.register(kind, Some(Box::new(filter)), Box::new(handler))
+    }
+
+    fn register(
+        &mut self,
+        kind: EventKind,
+        filter: Option<Filter<'a>>,
+        handler: Handler<'a>,
+    ) -> HandlerId {
+        let id = HandlerId(self.next_id);
+        self.next_id += 1;
+        self.registrations.push(Registration {
+            id,
+            kind,
+            filter,
+            handler,
+        });
+        id
+    }
+
+    /// Removes a handler. Returns `false` if it was already removed.
+    pub fn off(&mut self, id: HandlerId) -> bool {
+        let before = self.registrations.len();
+        self.registrations.retain(|r| r.id != id);
+        self.registrations.len() != before
+    }
+
+    pub fn push(&mut self, event: WebEvent) {
+        self.queue.push_back(event);
+    }
+
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Delivers every queued event and returns how many were delivered.
+    pub fn dispatch(&mut self) -> usize {
+        let mut delivered = 0;
+        while let Some(event) = self.queue.pop_front() {
+            let kind = event.kind();
+            for registration in self.registrations.iter_mut() {
+                if registration.kind != kind {
+                    continue;
+                }
+                if let Some(filter) = &registration.filter {
+                    if !filter(&event) {
+                        continue;
+                    }
+                }
+                (registration.handler)(&event);
+            }
+            if let Some(recording) = &mut self.recording {
+                recording.push(event);
+            }
+            delivered += 1;
+        }
+        delivered
+    }
+
+    /// Starts recording delivered events, discarding any earlier recording.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Vec::new());
+    }
+
+    /// Stops recording and returns the events delivered since `start_recording`.
+    pub fn stop_recording(&mut self) -> Vec<WebEvent> {
+        self.recording.take().unwrap_or_default()
+    }
+
+    /// Queues recorded events and delivers them.
+    pub fn replay<I>(&mut self, events: I) -> usize
+    where
+        I: IntoIterator<Item = WebEvent>,
+    {
+        self.queue.extend(events);
+        self.dispatch()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    fn session() -> Vec<WebEvent> {
+        vec![
+            WebEvent::PageLoad,
+            WebEvent::KeyPress('x'),
+            WebEvent::KeyPress(' '),
+            WebEvent::KeyPress('\n'),
+            WebEvent::Paste("my text\twith \\ escapes\n".to_owned()),
+            WebEvent::Click { x: 20, y: 80 },
+            WebEvent::Click { x: -5, y: 3 },
+            WebEvent::PageUnload,
+        ]
+    }
+
+    #[test]
+    fn delivers_in_order_to_matching_handlers() {
+        let seen = RefCell::new(Vec::new());
+        let mut bus = EventBus::new();
+        bus.on(EventKind::KeyPress, |e| {
+            seen.borrow_mut().push(format!("a {}", e))
+        });
+        bus.on(EventKind::Click, |e| {
+            seen.borrow_mut().push(format!("b {}", e))
+        });
+        bus.on(EventKind::KeyPress, |e| {
+            seen.borrow_mut().push(format!("c {}", e))
+        });
+
+        bus.push(WebEvent::KeyPress('q'));
+        bus.push(WebEvent::PageLoad);
+        bus.push(WebEvent::Click { x: 1, y: 2 });
+        assert_eq!(bus.pending(), 3);
+        assert_eq!(bus.dispatch(), 3);
+        assert_eq!(bus.pending(), 0);
+        drop(bus);
+
+        assert_eq!(seen.into_inner(), ["a key q", "c key q", "b click 1 2"]);
+    }
+
+    #[test]
+    fn removed_handlers_are_not_called() {
+        let mut count = 0;
+        {
+            let mut bus = EventBus::new();
+            let id = bus.on(EventKind::PageLoad, |_| count += 1);
+            bus.push(WebEvent::PageLoad);
+            bus.dispatch();
+            assert!(bus.off(id));
+            assert!(!bus.off(id));
+            bus.push(WebEvent::PageLoad);
+            bus.dispatch();
+        }
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn filters_clicks_by_region() {
+        let mut inside = Vec::new();
+        {
+            let region = Region {
+                x: 0,
+                y: 0,
+                width: 100,
+                height: 100,
+            };
+            let mut bus = EventBus::new();
+            bus.on_filtered(EventKind::Click, region.clicks(), |e| {
+                inside.push(e.clone())
+            });
+            bus.replay(session());
+        }
+        assert_eq!(inside, [WebEvent::Click { x: 20, y: 80 }]);
+    }
+
+    #[test]
+    fn regions_at_the_limits() {
+        let everything = Region {
+            x: i64::MIN,
+            y: i64::MIN,
+            width: i64::MAX,
+            height: i64::MAX,
+        };
+        assert!(everything.contains(i64::MIN, -2));
+        assert!(!everything.contains(-1, 0));
+        let corner = Region {
+            x: i64::MAX - 1,
+            y: i64::MAX - 1,
+            width: i64::MAX,
+            height: 1,
+        };
+        assert!(corner.contains(i64::MAX, i64::MAX - 1));
+        assert!(!corner.contains(i64::MAX, i64::MAX));
+        assert!(!corner.contains(0, i64::MAX - 1));
+        let empty = Region {
+            width: -5,
+            ..corner
+        };
+        assert!(!empty.contains(i64::MAX, i64::MAX - 1));
+    }
+
+    #[test]
+    fn log_round_trips() {
+        let log = to_log(&session());
+        assert_eq!(log.lines().count(), session().len());
+        assert_eq!(parse_log(&log), Ok(session()));
+    }
+
+    #[test]
+    fn parse_errors_carry_line_numbers() {
+        let err = parse_log("# recorded\nload\nclick 1\n").unwrap_err();
+        assert_eq!(err.line, 3);
+        assert_eq!(err.to_string(), "line 3: invalid event \"click 1\"");
+        for bad in ["", "key", "key ab", "paste bad\\q", "scroll 1", "load now"] {
+            assert!(bad.parse::<WebEvent>().is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn records_and_replays_sessions() {
+        let mut bus = EventBus::new();
+        bus.start_recording();
+        for event in session() {
+            bus.push(event);
+        }
+        bus.dispatch();
+        let recorded = bus.stop_recording();
+        assert_eq!(recorded, session());
+
+        let replayed = RefCell::new(Vec::new());
+        let mut player = EventBus::new();
+        for kind in [EventKind::KeyPress, EventKind::Paste] {
+            player.on(kind, |e| replayed.borrow_mut().push(e.clone()));
+        }
+        let log = to_log(&recorded);
+        assert_eq!(player.replay(parse_log(&log).unwrap()), recorded.len());
+        drop(player);
+        assert_eq!(replayed.into_inner().len(), 4);
+    }
+}
@@ -0,0 +1,5 @@
+// Custom types library
+//
+// Reusable versions of the examples in `main.rs`.
+
+pub mod event;
@@ -127,15 +127,9 @@ fn main() {
 // --------------------------------------------------------------------
 // section 02: enums ...
 /*
-enum WebEvent {
-    PageLoad,
-    PageUnload,
-    KeyPress(char),
-    Paste(String),
-    Click { x: i64, y: i64 },
-}
+use custom_types::event::{self, EventBus, EventKind, Region, WebEvent};
 
-fn inspect(web_evt: WebEvent) {
+fn inspect(web_evt: &WebEvent) {
     match web_evt {
         WebEvent::PageLoad => println!("page loaded"),
         WebEvent::PageUnload => println!("page unloaded"),
@@ -176,11 +170,30 @@ fn main() {
     let load = WebEvent::PageLoad;
     let unload = WebEvent::PageUnload;
 
-    inspect(pressed);
-    inspect(pasted);
-    inspect(click);
-    inspect(load);
-    inspect(unload);
+    // an event bus delivers queued events to the handlers of their variant
+    let mut bus = EventBus::new();
+    bus.on(EventKind::KeyPress, inspect);
+    bus.on(EventKind::Paste, inspect);
+    bus.on(EventKind::PageLoad, inspect);
+    bus.on(EventKind::PageUnload, inspect);
+    let region = Region {
+        x: 0,
+        y: 0,
+        width: 50,
+        height: 100,
+    };
+    bus.on_filtered(EventKind::Click, region.clicks(), inspect);
+
+    bus.start_recording();
+    for event in [pressed, pasted, click, load, unload] {
+        bus.push(event);
+    }
+    bus.dispatch();
+
+    // the recording can be saved as text and replayed later
+    let log = event::to_log(&bus.stop_recording());
+    print!("{}", log);
+    bus.replay(event::parse_log(&log).unwrap());
 
     let alias = Operations::Add;
 
@@ -0,0 +1,6 @@
+// Flow of control library
+//
+// Reusable versions of the examples in `main.rs`.
+
+pub mod shopping;
+pub mod temperature;
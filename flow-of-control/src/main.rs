@@ -405,22 +405,33 @@ fn main() {
 // section 09: match - guards
 
 /*
-#[allow(dead_code)]
-enum Temperature {
-    Celsius(i32),
-    Fahrenheit(i32),
-}
+use flow_of_control::temperature::{Celsius, Fahrenheit, Temperature, Threshold};
+use std::convert::TryFrom;
 
 fn main() {
-    let temperature = Temperature::Celsius(24);
-
-    match temperature {
-        Temperature::Celsius(c) if c > 30 => println!("{}C is above 30 celsius", c),
-        Temperature::Celsius(c) => println!("{}C is below 30 celsius", c),
-        Temperature::Fahrenheit(f) if f > 86 => println!("{}F is above 86 fahrenheit", f),
-        Temperature::Fahrenheit(f) => println!("{}F is below 86 fahrenheit", f),
+    // the guards `c > 30` and `f > 86` become a list of thresholds. the library
+    // compares across scales, so one threshold covers both.
+    let thresholds = [Threshold::new(
+        "above 30 celsius",
+        Temperature::try_from(Celsius(30.0)).unwrap(),
+    )];
+
+    let readings = [
+        Temperature::try_from(Celsius(24.0)).unwrap(),
+        Temperature::try_from(Fahrenheit(90.0)).unwrap(),
+        "75.2F".parse().unwrap(),
+    ];
+
+    for temperature in readings.iter() {
+        match temperature.classify(&thresholds) {
+            Some(threshold) => println!("{} is {}", temperature, threshold.label),
+            None => println!("{} is below every threshold", temperature),
+        }
     }
 
+    // below absolute zero is rejected by `TryFrom`
+    println!("{:?}", Temperature::try_from(Celsius(-300.0)));
+
     // Note that the compiler won't take guard conditions into account when checking
     // if all patterns are covered by the match expression.
     let number: u8 = 4;
@@ -559,19 +570,20 @@ fn main() {
 // section 12: let else
 
 /*
-use core::panic;
+use flow_of_control::shopping::{self, ItemError};
 use std::str::FromStr;
 
-fn get_count_item(s: &str) -> (u64, &str) {
+// instead of panicking, the `else` branches return an error to the caller.
+fn get_count_item(s: &str) -> Result<(u64, &str), ItemError> {
     let mut it = s.split(' ');
 
     // let else..
     let (Some(count_str), Some(item)) = (it.next(), it.next()) else {
-        panic!("Can't segment count item pair: '{s}'");
+        return Err(ItemError::MissingItem(s.to_string()));
     };
 
     let Ok(count) = u64::from_str(count_str) else {
-        panic!("Can't parse integer: '{count_str}'");
+        return Err(ItemError::InvalidQuantity(count_str.to_string()));
     };
 
     // previous
@@ -588,13 +600,25 @@ fn get_count_item(s: &str) -> (u64, &str) {
     };
     */
 
-    (count, item)
+    Ok((count, item))
 }
 
 fn main() {
-    // assert_eq!(get_count_item("3 chairs"), (3, "chairs"));
-    let (count, item) = get_count_item("3 chairs");
-    println!("{} and {}", count, item);
+    assert_eq!(get_count_item("3 chairs"), Ok((3, "chairs")));
+    match get_count_item("three chairs") {
+        Ok((count, item)) => println!("{} and {}", count, item),
+        Err(e) => println!("error: {}", e),
+    }
+
+    // the full parser handles units, fractions and plurals, and adds up duplicates
+    let list = shopping::parse_list("3 chairs\n2 kg flour\n1/2 kg flour\n1 chair").unwrap();
+    for entry in list.entries() {
+        println!("{}", entry);
+    }
+
+    if let Err(e) = shopping::parse_list("3 chairs\nsome flour") {
+        println!("{}", e);
+    }
 }
 */
 
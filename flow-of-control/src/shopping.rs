@@ -0,0 +1,522 @@
+// Shopping list
+//
+// Parses lines such as `"3 chairs"`, `"2 kg flour"`, `"1/2 cup sugar"` or
+// `"1.5 l milk"` into entries, and whole documents into a list where repeated
+// items are added together. Quantities are kept as exact fractions so that
+// `"1/3 cup"` three times is exactly one cup.
+
+use std::error;
+use std::fmt;
+use std::ops::Add;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItemError {
+    /// The line had no content.
+    Empty,
+    /// The first word was not a whole number, decimal or fraction.
+    InvalidQuantity(String),
+    /// The quantity was not followed by an item name.
+    MissingItem(String),
+    /// The quantity does not fit in the representation.
+    Overflow,
+}
+
+impl fmt::Display for ItemError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ItemError::Empty => write!(f, "empty entry"),
+            ItemError::InvalidQuantity(s) => write!(f, "can't parse quantity: '{}'", s),
+            ItemError::MissingItem(s) => write!(f, "no item after the quantity: '{}'", s),
+            ItemError::Overflow => write!(f, "quantity is too large"),
+        }
+    }
+}
+
+impl error::Error for ItemError {}
+
+/// An `ItemError` together with the 1-based line it occurred on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineError {
+    pub line: usize,
+    pub error: ItemError,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+impl error::Error for LineError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Quantity
+
+/// A non-negative fraction, always stored in lowest terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Quantity {
+    numer: u64,
+    denom: u64,
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+impl Quantity {
+    /// Returns `None` when `denom` is zero.
+    pub fn new(numer: u64, denom: u64) -> Option<Quantity> {
+        if denom == 0 {
+            return None;
+        }
+        let d = gcd(numer, denom);
+        Some(Quantity {
+            numer: numer / d,
+            denom: denom / d,
+        })
+    }
+
+    pub fn whole(n: u64) -> Quantity {
+        Quantity { numer: n, denom: 1 }
+    }
+
+    pub fn numer(&self) -> u64 {
+        self.numer
+    }
+
+    pub fn denom(&self) -> u64 {
+        self.denom
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.numer as f64 / self.denom as f64
+    }
+
+    pub fn checked_add(self, rhs: Quantity) -> Option<Quantity> {
+        let d = gcd(self.denom, rhs.denom);
+        let denom = (self.denom / d).checked_mul(rhs.denom)?;
+        let numer = self
+            .numer
+            .checked_mul(rhs.denom / d)?
+            .checked_add(rhs.numer.checked_mul(self.denom / d)?)?;
+        Quantity::new(numer, denom)
+    }
+}
+
+impl Add for Quantity {
+    type Output = Quantity;
+
+    fn add(self, rhs: Quantity) -> Quantity {
+        self.checked_add(rhs).expect("quantity overflow")
+    }
+}
+
+impl fmt::Display for Quantity {
+    // whole numbers print as `3`, the rest as mixed fractions such as `1 1/2`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let whole = self.numer / self.denom;
+        let rest = self.numer % self.denom;
+        match (whole, rest) {
+            (_, 0) => write!(f, "{}", whole),
+            (0, _) => write!(f, "{}/{}", rest, self.denom),
+            _ => write!(f, "{} {}/{}", whole, rest, self.denom),
+        }
+    }
+}
+
+impl FromStr for Quantity {
+    type Err = ItemError;
+
+    /// Parses a single word: `"3"`, `"1.25"` or `"3/4"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ItemError::InvalidQuantity(s.to_string());
+        let digits = |part: &str| {
+            if !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()) {
+                part.parse::<u64>().map_err(|_| ItemError::Overflow)
+            } else {
+                Err(invalid())
+            }
+        };
+
+        if let Some((numer, denom)) = s.split_once('/') {
+            Quantity::new(digits(numer)?, digits(denom)?).ok_or_else(invalid)
+        } else if let Some((whole, frac)) = s.split_once('.') {
+            let (whole, fraction) = (digits(whole)?, digits(frac)?);
+            let scale = 10u64
+                .checked_pow(frac.len() as u32)
+                .ok_or(ItemError::Overflow)?;
+            let numer = whole
+                .checked_mul(scale)
+                .and_then(|n| n.checked_add(fraction))
+                .ok_or(ItemError::Overflow)?;
+            Quantity::new(numer, scale).ok_or_else(invalid)
+        } else {
+            Ok(Quantity::whole(digits(s)?))
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Unit
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unit {
+    Kilogram,
+    Gram,
+    Pound,
+    Ounce,
+    Litre,
+    Millilitre,
+    Cup,
+    Tablespoon,
+    Teaspoon,
+    Dozen,
+    Pack,
+}
+
+impl Unit {
+    /// Recognises the usual abbreviations and singular/plural spellings.
+    pub fn from_word(word: &str) -> Option<Unit> {
+        let unit = match word.to_lowercase().as_str() {
+            "kg" | "kgs" | "kilo" | "kilos" | "kilogram" | "kilograms" => Unit::Kilogram,
+            "g" | "gram" | "grams" => Unit::Gram,
+            "lb" | "lbs" | "pound" | "pounds" => Unit::Pound,
+            "oz" | "ounce" | "ounces" => Unit::Ounce,
+            "l" | "litre" | "litres" | "liter" | "liters" => Unit::Litre,
+            "ml" | "millilitre" | "millilitres" | "milliliter" | "milliliters" => Unit::Millilitre,
+            "cup" | "cups" => Unit::Cup,
+            "tbsp" | "tablespoon" | "tablespoons" => Unit::Tablespoon,
+            "tsp" | "teaspoon" | "teaspoons" => Unit::Teaspoon,
+            "dozen" => Unit::Dozen,
+            "pack" | "packs" => Unit::Pack,
+            _ => return None,
+        };
+        Some(unit)
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Unit::Kilogram => "kg",
+            Unit::Gram => "g",
+            Unit::Pound => "lb",
+            Unit::Ounce => "oz",
+            Unit::Litre => "l",
+            Unit::Millilitre => "ml",
+            Unit::Cup => "cup",
+            Unit::Tablespoon => "tbsp",
+            Unit::Teaspoon => "tsp",
+            Unit::Dozen => "dozen",
+            Unit::Pack => "pack",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Entry
+
+// Singulars that the suffix rules below would cut too short: `cookies` is not
+// `cooky`, and `shoes` is not `sho`.
+const IE_SINGULARS: [&str; 6] = [
+    "brownie", "calorie", "cookie", "movie", "smoothie", "veggie",
+];
+const OE_SINGULARS: [&str; 8] = ["canoe", "doe", "floe", "hoe", "oboe", "roe", "shoe", "toe"];
+const IRREGULAR: [(&str, &str); 2] = [("quizzes", "quiz"), ("fezzes", "fez")];
+
+/// Reduces an English plural to its singular with the common suffix rules:
+/// `berries` -> `berry`, `boxes` -> `box`, `tomatoes` -> `tomato`, `chairs` -> `chair`,
+/// with exceptions for words such as `cookies`, `pies`, `shoes` and `sizes`.
+pub fn singular(word: &str) -> String {
+    let word = word.to_lowercase();
+    if let Some((_, singular)) = IRREGULAR.iter().find(|(plural, _)| *plural == word) {
+        return singular.to_string();
+    }
+    // the word with its trailing `s` or `es` removed
+    let drop = |n: usize| word[..word.len() - n].to_string();
+    if let Some(stem) = word.strip_suffix("ies") {
+        // `pies` and `ties`: a short stem keeps its `ie`
+        if stem.len() <= 2 || IE_SINGULARS.contains(&&*drop(1)) {
+            drop(1)
+        } else {
+            format!("{}y", stem)
+        }
+    } else if word.ends_with("oes") {
+        if OE_SINGULARS.contains(&&*drop(1)) {
+            drop(1)
+        } else {
+            drop(2)
+        }
+    } else if word.ends_with("zes") {
+        // `buzzes` -> `buzz`, but `sizes` -> `size`
+        if word.ends_with("zzes") {
+            drop(2)
+        } else {
+            drop(1)
+        }
+    } else if ["ches", "shes", "sses", "xes"]
+        .iter()
+        .any(|suffix| word.ends_with(suffix))
+    {
+        drop(2)
+    } else if word.len() > 1
+        && word.ends_with('s')
+        && !word.ends_with("ss")
+        && !word.ends_with("us")
+    {
+        drop(1)
+    } else {
+        word
+    }
+}
+
+/// One line of a shopping list. The item name is lowercase and singular so that
+/// `"2 Chairs"` and `"1 chair"` name the same item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub quantity: Quantity,
+    pub unit: Option<Unit>,
+    pub item: String,
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.quantity)?;
+        if let Some(unit) = self.unit {
+            write!(f, " {}", unit)?;
+        }
+        write!(f, " {}", self.item)
+    }
+}
+
+impl FromStr for Entry {
+    type Err = ItemError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_entry(s)
+    }
+}
+
+/// Parses `"<quantity> [unit] [of] <item words>"`. The quantity may be a mixed
+/// number written as two words, as in `"1 1/2 cups flour"`.
+pub fn parse_entry(s: &str) -> Result<Entry, ItemError> {
+    let mut words: Vec<&str> = s.split_whitespace().collect();
+    if words.is_empty() {
+        return Err(ItemError::Empty);
+    }
+
+    let mut quantity: Quantity = words.remove(0).parse()?;
+    if quantity.denom == 1 && words.first().is_some_and(|w| w.contains('/')) {
+        let fraction: Quantity = words.remove(0).parse()?;
+        quantity = quantity.checked_add(fraction).ok_or(ItemError::Overflow)?;
+    }
+
+    // a unit word only counts as a unit when something follows it: `"2 cups"`
+    // is two of the item "cup".
+    let mut unit = None;
+    if words.len() > 1 {
+        if let Some(u) = Unit::from_word(words[0]) {
+            unit = Some(u);
+            words.remove(0);
+        }
+    }
+    if words.len() > 1 && words[0].eq_ignore_ascii_case("of") {
+        words.remove(0);
+    }
+
+    let Some((last, rest)) = words.split_last() else {
+        return Err(ItemError::MissingItem(s.trim().to_string()));
+    };
+    let mut item: Vec<String> = rest.iter().map(|w| w.to_lowercase()).collect();
+    item.push(singular(last));
+
+    Ok(Entry {
+        quantity,
+        unit,
+        item: item.join(" "),
+    })
+}
+
+// --------------------------------------------------------------------------------
+// ShoppingList
+
+/// Entries in order of first appearance, with duplicates of the same item and
+/// unit added together.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShoppingList {
+    entries: Vec<Entry>,
+}
+
+impl ShoppingList {
+    pub fn new() -> ShoppingList {
+        ShoppingList::default()
+    }
+
+    pub fn add(&mut self, entry: Entry) -> Result<(), ItemError> {
+        let existing = self
+            .entries
+            .iter_mut()
+            .find(|e| e.item == entry.item && e.unit == entry.unit);
+        match existing {
+            Some(e) => {
+                e.quantity = e
+                    .quantity
+                    .checked_add(entry.quantity)
+                    .ok_or(ItemError::Overflow)?
+            }
+            None => self.entries.push(entry),
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, item: &str, unit: Option<Unit>) -> Option<Quantity> {
+        self.entries
+            .iter()
+            .find(|e| e.item == item && e.unit == unit)
+            .map(|e| e.quantity)
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Parses a document with one entry per line. Blank lines and lines starting
+/// with `#` are skipped. The first bad line is reported with its line number.
+pub fn parse_list(text: &str) -> Result<ShoppingList, LineError> {
+    let mut list = ShoppingList::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        parse_entry(line)
+            .and_then(|entry| list.add(entry))
+            .map_err(|error| LineError {
+                line: index + 1,
+                error,
+            })?;
+    }
+    Ok(list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn q(numer: u64, denom: u64) -> Quantity {
+        Quantity::new(numer, denom).unwrap()
+    }
+
+    #[test]
+    fn parses_quantities() {
+        assert_eq!("3".parse(), Ok(q(3, 1)));
+        assert_eq!("1.25".parse(), Ok(q(5, 4)));
+        assert_eq!("6/8".parse(), Ok(q(3, 4)));
+        assert_eq!(q(3, 2).to_string(), "1 1/2");
+        for bad in ["", "x", "-1", "1/0", "1.", ".5", "1/2/3", "1e3"] {
+            assert!(bad.parse::<Quantity>().is_err(), "{:?}", bad);
+        }
+        assert_eq!(
+            "99999999999999999999".parse::<Quantity>(),
+            Err(ItemError::Overflow)
+        );
+    }
+
+    #[test]
+    fn parses_entries() {
+        let entry = parse_entry("3 chairs").unwrap();
+        assert_eq!(entry.quantity, q(3, 1));
+        assert_eq!(entry.unit, None);
+        assert_eq!(entry.item, "chair");
+
+        let entry = parse_entry("2 kg of Plain Flour").unwrap();
+        assert_eq!(entry.unit, Some(Unit::Kilogram));
+        assert_eq!(entry.item, "plain flour");
+
+        let entry = parse_entry("1 1/2 cups brown sugar").unwrap();
+        assert_eq!(entry.to_string(), "1 1/2 cup brown sugar");
+
+        assert_eq!(parse_entry("2 cups").unwrap().item, "cup");
+    }
+
+    #[test]
+    fn reports_errors_instead_of_panicking() {
+        assert_eq!(parse_entry("   "), Err(ItemError::Empty));
+        assert_eq!(
+            parse_entry("three chairs"),
+            Err(ItemError::InvalidQuantity("three".to_string()))
+        );
+        assert_eq!(
+            parse_entry("3"),
+            Err(ItemError::MissingItem("3".to_string()))
+        );
+    }
+
+    #[test]
+    fn singularises_common_plurals() {
+        let cases = [
+            ("chairs", "chair"),
+            ("berries", "berry"),
+            ("boxes", "box"),
+            ("tomatoes", "tomato"),
+            ("peaches", "peach"),
+            ("glass", "glass"),
+            ("asparagus", "asparagus"),
+            ("rice", "rice"),
+            ("cookies", "cookie"),
+            ("pies", "pie"),
+            ("ties", "tie"),
+            ("shoes", "shoe"),
+            ("canoes", "canoe"),
+            ("potatoes", "potato"),
+            ("sizes", "size"),
+            ("prizes", "prize"),
+            ("buzzes", "buzz"),
+            ("quizzes", "quiz"),
+        ];
+        for (plural, expected) in cases {
+            assert_eq!(singular(plural), expected);
+        }
+    }
+
+    #[test]
+    fn aggregates_documents() {
+        let text = "# weekly\n3 chairs\n1/3 cup milk\n\n1 chair\n1/3 cup milk\n1/3 cups milk\n";
+        let list = parse_list(text).unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.get("chair", None), Some(q(4, 1)));
+        assert_eq!(list.get("milk", Some(Unit::Cup)), Some(q(1, 1)));
+
+        let list = parse_list("2 cookies\n1 cookie\n1 pie\n2 pies\n").unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.get("cookie", None), Some(q(3, 1)));
+        assert_eq!(list.get("pie", None), Some(q(3, 1)));
+    }
+
+    #[test]
+    fn reports_line_numbers() {
+        let err = parse_list("3 chairs\n\nlots of chairs\n").unwrap_err();
+        assert_eq!(err.line, 3);
+        assert_eq!(err.to_string(), "line 3: can't parse quantity: 'lots'");
+    }
+}
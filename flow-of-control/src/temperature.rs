@@ -0,0 +1,312 @@
+// Temperature
+//
+// A `Temperature` is stored as an integer number of ticks above absolute zero,
+// where one tick is 1/900 kelvin. With that unit every value given to two
+// decimal places in Celsius, Fahrenheit or Kelvin is represented exactly, so
+// conversions between scales are lossless and comparisons across scales are
+// exact: `30C == 86F`.
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+const TICKS_PER_KELVIN: i64 = 900;
+const TICKS_PER_FAHRENHEIT: i64 = 500;
+// 0C is 273.15K and 0F is 459.67R, both in hundredths of a degree.
+const CELSIUS_OFFSET: i64 = 27315;
+const FAHRENHEIT_OFFSET: i64 = 45967;
+// keeps `value * 100` well inside the range of `i64` ticks.
+const MAX_DEGREES: f64 = 1e12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scale {
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+impl Scale {
+    fn suffix(self) -> char {
+        match self {
+            Scale::Celsius => 'C',
+            Scale::Fahrenheit => 'F',
+            Scale::Kelvin => 'K',
+        }
+    }
+
+    fn ticks_from_hundredths(self, hundredths: i64) -> i64 {
+        match self {
+            Scale::Celsius => (hundredths + CELSIUS_OFFSET) * (TICKS_PER_KELVIN / 100),
+            Scale::Fahrenheit => (hundredths + FAHRENHEIT_OFFSET) * (TICKS_PER_FAHRENHEIT / 100),
+            Scale::Kelvin => hundredths * (TICKS_PER_KELVIN / 100),
+        }
+    }
+
+    fn degrees_from_ticks(self, ticks: i64) -> f64 {
+        let hundredths = match self {
+            Scale::Celsius => ticks as f64 / 9.0 - CELSIUS_OFFSET as f64,
+            Scale::Fahrenheit => ticks as f64 / 5.0 - FAHRENHEIT_OFFSET as f64,
+            Scale::Kelvin => ticks as f64 / 9.0,
+        };
+        hundredths / 100.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemperatureError {
+    /// The value is colder than 0K.
+    BelowAbsoluteZero(f64, Scale),
+    /// The value is infinite, `NaN` or too large to represent.
+    OutOfRange(f64),
+    /// The input was not a number followed by `C`, `F` or `K`.
+    Parse(String),
+}
+
+impl fmt::Display for TemperatureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TemperatureError::BelowAbsoluteZero(value, scale) => {
+                write!(f, "{}{} is below absolute zero", value, scale.suffix())
+            }
+            TemperatureError::OutOfRange(value) => {
+                write!(f, "{} is not a representable temperature", value)
+            }
+            TemperatureError::Parse(input) => {
+                write!(
+                    f,
+                    "expected a temperature such as \"24C\", found {:?}",
+                    input
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for TemperatureError {}
+
+/// An absolute temperature, remembering the scale it was created in for display.
+/// Equality and ordering compare the physical temperature only.
+#[derive(Debug, Clone, Copy)]
+pub struct Temperature {
+    ticks: i64,
+    scale: Scale,
+}
+
+impl Temperature {
+    pub const ABSOLUTE_ZERO: Temperature = Temperature {
+        ticks: 0,
+        scale: Scale::Kelvin,
+    };
+
+    /// Builds a temperature from `value` degrees on `scale`, rounded to
+    /// hundredths of a degree.
+    pub fn new(value: f64, scale: Scale) -> Result<Temperature, TemperatureError> {
+        if !value.is_finite() || value.abs() > MAX_DEGREES {
+            return Err(TemperatureError::OutOfRange(value));
+        }
+        // checked before rounding, which would turn -0.004K into 0K.
+        if value < scale.degrees_from_ticks(0) {
+            return Err(TemperatureError::BelowAbsoluteZero(value, scale));
+        }
+        let ticks = scale.ticks_from_hundredths((value * 100.0).round() as i64);
+        Ok(Temperature { ticks, scale })
+    }
+
+    pub fn scale(&self) -> Scale {
+        self.scale
+    }
+
+    /// The value in degrees on `scale`.
+    pub fn degrees(&self, scale: Scale) -> f64 {
+        scale.degrees_from_ticks(self.ticks)
+    }
+
+    /// The same temperature, displayed in `scale`.
+    pub fn to_scale(self, scale: Scale) -> Temperature {
+        Temperature { scale, ..self }
+    }
+
+    /// Returns the highest threshold this temperature is above, if any.
+    /// The thresholds may be given in any order and on any scale.
+    pub fn classify<'a>(&self, thresholds: &'a [Threshold]) -> Option<&'a Threshold> {
+        thresholds
+            .iter()
+            .filter(|threshold| *self > threshold.limit)
+            .max_by_key(|threshold| threshold.limit)
+    }
+}
+
+impl PartialEq for Temperature {
+    fn eq(&self, other: &Self) -> bool {
+        self.ticks == other.ticks
+    }
+}
+
+impl Eq for Temperature {}
+
+impl PartialOrd for Temperature {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Temperature {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.ticks.cmp(&other.ticks)
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hundredths = (self.degrees(self.scale) * 100.0).round();
+        write!(f, "{}{}", hundredths / 100.0, self.scale.suffix())
+    }
+}
+
+impl FromStr for Temperature {
+    type Err = TemperatureError;
+
+    /// Parses `"24C"`, `"75.2F"` or `"300K"`. A degree sign and whitespace
+    /// before the unit are allowed, as in `"24 °C"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_error = || TemperatureError::Parse(s.to_string());
+        let trimmed = s.trim();
+        let unit = trimmed.chars().last().ok_or_else(parse_error)?;
+        let scale = match unit.to_ascii_uppercase() {
+            'C' => Scale::Celsius,
+            'F' => Scale::Fahrenheit,
+            'K' => Scale::Kelvin,
+            _ => return Err(parse_error()),
+        };
+        let number = trimmed[..trimmed.len() - 1].trim_end();
+        let number = number.strip_suffix('°').unwrap_or(number).trim_end();
+        let value = number.parse::<f64>().map_err(|_| parse_error())?;
+        Temperature::new(value, scale)
+    }
+}
+
+// Scale-tagged values, the inputs and outputs of the fallible conversions.
+macro_rules! scale_type {
+    ($name:ident, $scale:expr) => {
+        #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+        pub struct $name(pub f64);
+
+        impl TryFrom<$name> for Temperature {
+            type Error = TemperatureError;
+
+            fn try_from(value: $name) -> Result<Self, Self::Error> {
+                Temperature::new(value.0, $scale)
+            }
+        }
+
+        impl From<Temperature> for $name {
+            fn from(t: Temperature) -> $name {
+                $name(t.degrees($scale))
+            }
+        }
+    };
+}
+
+scale_type!(Celsius, Scale::Celsius);
+scale_type!(Fahrenheit, Scale::Fahrenheit);
+scale_type!(Kelvin, Scale::Kelvin);
+
+/// A named lower limit used by `Temperature::classify`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Threshold {
+    pub label: String,
+    pub limit: Temperature,
+}
+
+impl Threshold {
+    pub fn new(label: &str, limit: Temperature) -> Threshold {
+        Threshold {
+            label: label.to_string(),
+            limit,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(s: &str) -> Temperature {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn compares_across_scales() {
+        assert_eq!(t("30C"), t("86F"));
+        assert_eq!(t("0K"), Temperature::ABSOLUTE_ZERO);
+        assert_eq!(t("-273.15C"), t("-459.67F"));
+        assert!(t("24C") < t("75.3F"));
+        assert_eq!(t("24C"), t("75.2F"));
+    }
+
+    #[test]
+    fn conversions_are_lossless() {
+        for hundredths in -27315..=10_000 {
+            let c = hundredths as f64 / 100.0;
+            let celsius = Temperature::try_from(Celsius(c)).unwrap();
+            let fahrenheit = celsius.to_scale(Scale::Fahrenheit);
+            assert_eq!(celsius, fahrenheit);
+            assert_eq!(
+                fahrenheit.to_scale(Scale::Celsius).to_string(),
+                format!("{}C", c)
+            );
+            assert!((Fahrenheit::from(celsius).0 - (c * 1.8 + 32.0)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn rejects_below_absolute_zero() {
+        assert_eq!(
+            Temperature::try_from(Kelvin(-0.01)),
+            Err(TemperatureError::BelowAbsoluteZero(-0.01, Scale::Kelvin))
+        );
+        assert!(Temperature::try_from(Celsius(-273.16)).is_err());
+        assert!(Temperature::try_from(Kelvin(-0.004)).is_err());
+        assert!(Temperature::try_from(Celsius(-273.154)).is_err());
+        assert!(Temperature::try_from(Fahrenheit(-459.674)).is_err());
+        assert_eq!(
+            Temperature::try_from(Kelvin(0.0)),
+            Ok(Temperature::ABSOLUTE_ZERO)
+        );
+        assert_eq!(
+            Temperature::try_from(Celsius(-273.15)),
+            Ok(Temperature::ABSOLUTE_ZERO)
+        );
+        assert_eq!(
+            Temperature::try_from(Fahrenheit(-459.67)),
+            Ok(Temperature::ABSOLUTE_ZERO)
+        );
+        assert!(Temperature::try_from(Fahrenheit(f64::NAN)).is_err());
+    }
+
+    #[test]
+    fn parses_and_displays() {
+        assert_eq!(t("75.2F").to_string(), "75.2F");
+        assert_eq!(t(" 24 °c").to_string(), "24C");
+        assert_eq!(t("24C").to_scale(Scale::Kelvin).to_string(), "297.15K");
+        assert!("24".parse::<Temperature>().is_err());
+        assert!("hotC".parse::<Temperature>().is_err());
+    }
+
+    #[test]
+    fn classify_picks_highest_exceeded_threshold() {
+        let thresholds = [
+            Threshold::new("hot", t("30C")),
+            Threshold::new("freezing", t("32F")),
+            Threshold::new("mild", t("15C")),
+        ];
+        let label = |s| t(s).classify(&thresholds).map(|th| th.label.as_str());
+        assert_eq!(label("24C"), Some("mild"));
+        assert_eq!(label("87F"), Some("hot"));
+        // the guard is strict, like `c > 30`.
+        assert_eq!(label("86F"), Some("mild"));
+        assert_eq!(label("-5C"), None);
+    }
+}
@@ -0,0 +1,400 @@
+// Callbacks
+//
+// A registry of boxed closures, grown from the closure sections (03-10).
+// `Fn`, `FnMut` and one-shot `FnOnce` handlers can be registered with a
+// priority and removed again by handle. Every handler is stored as a boxed
+// `FnMut`: an `Fn` is also an `FnMut`, and an `FnOnce` is wrapped in an
+// `Option` it is taken out of on the first call.
+//
+// `Registry<E>` accepts any closure. `SendRegistry<E>` only accepts closures
+// whose captured state is `Send`, and is therefore `Send` itself.
+//
+// The second half of the module has combinators for building handlers:
+// `compose`, `and_then`, `memoize`, `retry` and `debounce`.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+/// The boxed closure types a registry can store.
+pub trait Handler<E> {
+    fn call(&mut self, event: &E);
+}
+
+impl<E> Handler<E> for dyn FnMut(&E) + '_ {
+    fn call(&mut self, event: &E) {
+        self(event)
+    }
+}
+
+impl<E> Handler<E> for dyn FnMut(&E) + Send + '_ {
+    fn call(&mut self, event: &E) {
+        self(event)
+    }
+}
+
+/// Boxes a closure as the handler type `H`.
+pub trait IntoHandler<H: ?Sized> {
+    fn into_handler(self) -> Box<H>;
+}
+
+impl<'a, E, F: FnMut(&E) + 'a> IntoHandler<dyn FnMut(&E) + 'a> for F {
+    fn into_handler(self) -> Box<dyn FnMut(&E) + 'a> {
+        Box::new(self)
+    }
+}
+
+impl<'a, E, F: FnMut(&E) + Send + 'a> IntoHandler<dyn FnMut(&E) + Send + 'a> for F {
+    fn into_handler(self) -> Box<dyn FnMut(&E) + Send + 'a> {
+        Box::new(self)
+    }
+}
+
+/// Which closure trait a handler was registered as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Fn,
+    FnMut,
+    FnOnce,
+}
+
+/// Returned on registration, used to remove the handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HandlerId(u64);
+
+struct Entry<H: ?Sized> {
+    id: HandlerId,
+    priority: i32,
+    kind: Kind,
+    handler: Box<H>,
+}
+
+/// Handlers run from the highest priority to the lowest; handlers with the
+/// same priority run in registration order.
+/// `'a` bounds what the handlers may borrow.
+pub struct Registry<'a, E, H: ?Sized = dyn FnMut(&E) + 'a> {
+    entries: Vec<Entry<H>>,
+    next_id: u64,
+    _lifetime: PhantomData<&'a ()>,
+    _event: PhantomData<fn(&E)>,
+}
+
+pub type SendRegistry<'a, E> = Registry<'a, E, dyn FnMut(&E) + Send + 'a>;
+
+impl<E, H: ?Sized + Handler<E>> Default for Registry<'_, E, H> {
+    fn default() -> Self {
+        Registry {
+            entries: Vec::new(),
+            next_id: 0,
+            _lifetime: PhantomData,
+            _event: PhantomData,
+        }
+    }
+}
+
+impl<'a, E> Registry<'a, E> {
+    /// A registry for any closures. Use `SendRegistry::default()` for one that
+    /// can move between threads.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<E, H: ?Sized + Handler<E>> Registry<'_, E, H> {
+    fn insert(&mut self, priority: i32, kind: Kind, handler: Box<H>) -> HandlerId {
+        let id = HandlerId(self.next_id);
+        self.next_id += 1;
+        // after every entry with the same or a higher priority.
+        let at = self
+            .entries
+            .iter()
+            .position(|e| e.priority < priority)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(
+            at,
+            Entry {
+                id,
+                priority,
+                kind,
+                handler,
+            },
+        );
+        id
+    }
+
+    pub fn add_fn<F>(&mut self, priority: i32, f: F) -> HandlerId
+    where
+        F: Fn(&E) + IntoHandler<H>,
+    {
+        self.insert(priority, Kind::Fn, f.into_handler())
+    }
+
+    pub fn add_fn_mut<F>(&mut self, priority: i32, f: F) -> HandlerId
+    where
+        F: FnMut(&E) + IntoHandler<H>,
+    {
+        self.insert(priority, Kind::FnMut, f.into_handler())
+    }
+
+    /// Registers a handler that runs on the next `emit` and is then removed.
+    pub fn add_once<F>(&mut self, priority: i32, f: F) -> HandlerId
+    where
+        F: FnOnce(&E),
+        OnceHandler<F>: IntoHandler<H>,
+    {
+        self.insert(priority, Kind::FnOnce, OnceHandler(Some(f)).into_handler())
+    }
+
+    /// Returns `false` if the handler was already removed or has run once.
+    pub fn remove(&mut self, id: HandlerId) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|e| e.id != id);
+        self.entries.len() != before
+    }
+
+    pub fn contains(&self, id: HandlerId) -> bool {
+        self.entries.iter().any(|e| e.id == id)
+    }
+
+    pub fn kind(&self, id: HandlerId) -> Option<Kind> {
+        self.entries.iter().find(|e| e.id == id).map(|e| e.kind)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Calls every handler with `event` and returns how many ran.
+    pub fn emit(&mut self, event: &E) -> usize {
+        for entry in self.entries.iter_mut() {
+            entry.handler.call(event);
+        }
+        let ran = self.entries.len();
+        self.entries.retain(|e| e.kind != Kind::FnOnce);
+        ran
+    }
+}
+
+/// An `FnOnce` made callable through `FnMut`: it runs the first time only.
+pub struct OnceHandler<F>(Option<F>);
+
+impl<'a, E, F: FnOnce(&E) + 'a> IntoHandler<dyn FnMut(&E) + 'a> for OnceHandler<F> {
+    fn into_handler(mut self) -> Box<dyn FnMut(&E) + 'a> {
+        Box::new(move |event: &E| {
+            if let Some(f) = self.0.take() {
+                f(event)
+            }
+        })
+    }
+}
+
+impl<'a, E, F: FnOnce(&E) + Send + 'a> IntoHandler<dyn FnMut(&E) + Send + 'a> for OnceHandler<F> {
+    fn into_handler(mut self) -> Box<dyn FnMut(&E) + Send + 'a> {
+        Box::new(move |event: &E| {
+            if let Some(f) = self.0.take() {
+                f(event)
+            }
+        })
+    }
+}
+
+// --------------------------------------------------------------------------------
+// combinators
+
+/// `compose(f, g)(x) == g(f(x))`.
+pub fn compose<A, B, C, F, G>(f: F, g: G) -> impl Fn(A) -> C
+where
+    F: Fn(A) -> B,
+    G: Fn(B) -> C,
+{
+    move |x| g(f(x))
+}
+
+/// Chains two fallible steps; `g` only runs if `f` succeeded.
+pub fn and_then<A, B, C, Err, F, G>(f: F, g: G) -> impl Fn(A) -> Result<C, Err>
+where
+    F: Fn(A) -> Result<B, Err>,
+    G: Fn(B) -> Result<C, Err>,
+{
+    move |x| f(x).and_then(&g)
+}
+
+/// Caches the result of `f` for every argument it has seen.
+pub fn memoize<A, B, F>(f: F) -> impl FnMut(A) -> B
+where
+    A: Hash + Eq + Clone,
+    B: Clone,
+    F: Fn(A) -> B,
+{
+    let mut cache = HashMap::new();
+    move |x: A| cache.entry(x.clone()).or_insert_with(|| f(x)).clone()
+}
+
+/// Calls `f` up to `attempts` times until it succeeds, returning the last error.
+///
+/// # Panics
+///
+/// Panics if `attempts` is zero.
+pub fn retry<T, Err, F>(attempts: usize, mut f: F) -> impl FnMut() -> Result<T, Err>
+where
+    F: FnMut() -> Result<T, Err>,
+{
+    assert!(attempts > 0, "retry needs at least one attempt");
+    move || {
+        let mut result = f();
+        for _ in 1..attempts {
+            if result.is_ok() {
+                break;
+            }
+            result = f();
+        }
+        result
+    }
+}
+
+/// Debounce by count: `f` runs on every `n`th call, with that call's argument,
+/// and the calls in between are dropped.
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+pub fn debounce<A, F>(n: usize, mut f: F) -> impl FnMut(A) -> bool
+where
+    F: FnMut(A),
+{
+    assert!(n > 0, "debounce needs a count of at least one");
+    let mut calls = 0;
+    move |x| {
+        calls += 1;
+        if calls == n {
+            calls = 0;
+            f(x);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    #[test]
+    fn runs_by_priority_then_registration_order() {
+        let log = RefCell::new(Vec::new());
+        let mut registry: Registry<u32> = Registry::new();
+        registry.add_fn(0, |n| log.borrow_mut().push(format!("low {}", n)));
+        registry.add_fn(10, |n| log.borrow_mut().push(format!("high {}", n)));
+        registry.add_fn(0, |n| log.borrow_mut().push(format!("low again {}", n)));
+
+        assert_eq!(registry.emit(&1), 3);
+        drop(registry);
+        assert_eq!(log.into_inner(), ["high 1", "low 1", "low again 1"]);
+    }
+
+    #[test]
+    fn fn_mut_keeps_state_and_fn_once_runs_once() {
+        let mut total = 0;
+        let mut once = Vec::new();
+        {
+            let mut registry = Registry::new();
+            let sum = registry.add_fn_mut(0, |n: &u32| total += n);
+            let first = registry.add_once(0, |n: &u32| once.push(*n));
+            assert_eq!(registry.kind(sum), Some(Kind::FnMut));
+            assert_eq!(registry.kind(first), Some(Kind::FnOnce));
+
+            registry.emit(&1);
+            assert!(!registry.contains(first));
+            assert_eq!(registry.emit(&2), 1);
+            assert!(registry.remove(sum));
+            assert!(!registry.remove(sum));
+            assert_eq!(registry.emit(&3), 0);
+        }
+        assert_eq!(total, 3);
+        assert_eq!(once, [1]);
+    }
+
+    #[test]
+    fn send_registry_moves_across_threads() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut registry = SendRegistry::<String>::default();
+        let sink = Arc::clone(&seen);
+        registry.add_fn(0, move |s| sink.lock().unwrap().push(s.clone()));
+        let owned = String::from("once");
+        registry.add_once(1, move |s| assert_ne!(*s, owned));
+        // registry.add_fn(0, { let rc = std::rc::Rc::new(1); move |_| drop(&rc) }); // ERROR: not `Send`
+
+        thread::spawn(move || {
+            registry.emit(&"a".to_string());
+            registry.emit(&"b".to_string());
+        })
+        .join()
+        .unwrap();
+        assert_eq!(*seen.lock().unwrap(), ["a", "b"]);
+    }
+
+    #[test]
+    fn compose_and_then() {
+        let double_then_show = compose(|x: i32| x * 2, |x: i32| x.to_string());
+        assert_eq!(double_then_show(21), "42");
+
+        let parse = |s: &str| s.parse::<i32>().map_err(|_| "not a number");
+        let positive = |n: i32| if n > 0 { Ok(n) } else { Err("not positive") };
+        let checked = and_then(parse, positive);
+        assert_eq!(checked("5"), Ok(5));
+        assert_eq!(checked("-5"), Err("not positive"));
+        assert_eq!(checked("five"), Err("not a number"));
+    }
+
+    #[test]
+    fn memoize_calls_once_per_argument() {
+        let calls = RefCell::new(0);
+        let mut square = memoize(|x: u64| {
+            *calls.borrow_mut() += 1;
+            x * x
+        });
+        assert_eq!(square(4), 16);
+        assert_eq!(square(4), 16);
+        assert_eq!(square(5), 25);
+        drop(square);
+        assert_eq!(calls.into_inner(), 2);
+    }
+
+    #[test]
+    fn retry_stops_at_first_success() {
+        let mut attempts = 0;
+        let mut flaky = retry(5, || {
+            attempts += 1;
+            if attempts < 3 {
+                Err(attempts)
+            } else {
+                Ok("done")
+            }
+        });
+        assert_eq!(flaky(), Ok("done"));
+        drop(flaky);
+        assert_eq!(attempts, 3);
+
+        let mut failing = retry(2, || Err::<(), _>("down"));
+        assert_eq!(failing(), Err("down"));
+    }
+
+    #[test]
+    fn debounce_by_count() {
+        let mut fired = Vec::new();
+        {
+            let mut every_third = debounce(3, |x: i32| fired.push(x));
+            let results: Vec<bool> = (1..=7).map(&mut every_third).collect();
+            assert_eq!(results, [false, false, true, false, false, true, false]);
+        }
+        assert_eq!(fired, [3, 6]);
+    }
+}
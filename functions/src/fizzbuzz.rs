@@ -0,0 +1,333 @@
+// FizzBuzz rule engine
+//
+// `fizzbuzz` from section 01 with the rules taken out of the code. An `Engine`
+// holds an ordered list of rules; a number is replaced by the words of every
+// rule it matches, joined in rule order, or printed as itself if none match.
+// `Engine::classic()` gives the original 3/`fizz`, 5/`buzz` output.
+//
+// Rule files have one rule per line, `#` starts a comment:
+//
+//     3 fizz              # divisible by 3
+//     prime zap           # a prime number
+//     square sq           # a perfect square
+//     contains 7 seven    # has the digit 7
+
+use std::error;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::thread;
+
+/// `false` for a zero divisor instead of panicking.
+pub fn is_divisible_by(lhs: u64, rhs: u64) -> bool {
+    if rhs == 0 {
+        return false;
+    }
+
+    lhs.is_multiple_of(rhs)
+}
+
+pub fn is_prime(n: u64) -> bool {
+    n >= 2
+        && (2..)
+            .take_while(|&d| d <= n / d)
+            .all(|d| !n.is_multiple_of(d))
+}
+
+pub fn is_square(n: u64) -> bool {
+    let root = (n as f64).sqrt() as u64;
+    (root.saturating_sub(1)..=root + 1).any(|r| r.checked_mul(r) == Some(n))
+}
+
+pub fn contains_digit(n: u64, digit: u8) -> bool {
+    n.to_string().bytes().any(|b| b == b'0' + digit)
+}
+
+type Predicate = Box<dyn Fn(u64) -> bool + Send + Sync>;
+
+pub enum Rule {
+    Divisor {
+        divisor: u64,
+        word: String,
+    },
+    Predicate {
+        name: String,
+        word: String,
+        test: Predicate,
+    },
+}
+
+impl Rule {
+    pub fn divisor(divisor: u64, word: &str) -> Rule {
+        Rule::Divisor {
+            divisor,
+            word: word.to_string(),
+        }
+    }
+
+    /// `name` is only used when printing the rule.
+    pub fn predicate<F>(name: &str, word: &str, test: F) -> Rule
+    where
+        F: Fn(u64) -> bool + Send + Sync + 'static,
+    {
+        Rule::Predicate {
+            name: name.to_string(),
+            word: word.to_string(),
+            test: Box::new(test),
+        }
+    }
+
+    pub fn word(&self) -> &str {
+        match self {
+            Rule::Divisor { word, .. } | Rule::Predicate { word, .. } => word,
+        }
+    }
+
+    pub fn matches(&self, n: u64) -> bool {
+        match self {
+            Rule::Divisor { divisor, .. } => is_divisible_by(n, *divisor),
+            Rule::Predicate { test, .. } => test(n),
+        }
+    }
+}
+
+impl fmt::Debug for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rule::Divisor { divisor, word } => write!(f, "{} {}", divisor, word),
+            Rule::Predicate { name, word, .. } => write!(f, "{} {}", name, word),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl error::Error for RuleError {}
+
+fn parse_rule(line: &str) -> Result<Rule, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let rule = match words.as_slice() {
+        ["prime", word] => Rule::predicate("prime", word, is_prime),
+        ["square", word] => Rule::predicate("square", word, is_square),
+        ["contains", digit, word] => {
+            let digit = match digit.parse::<u8>() {
+                Ok(d) if d < 10 => d,
+                _ => return Err(format!("'{}' is not a digit", digit)),
+            };
+            Rule::predicate(&format!("contains {}", digit), word, move |n| {
+                contains_digit(n, digit)
+            })
+        }
+        [divisor, word] => match divisor.parse::<u64>() {
+            Ok(0) => return Err("divisor must not be zero".to_string()),
+            Ok(d) => Rule::divisor(d, word),
+            Err(_) => return Err(format!("unknown rule '{}'", divisor)),
+        },
+        _ => return Err(format!("expected '<rule> <word>', found '{}'", line)),
+    };
+    Ok(rule)
+}
+
+#[derive(Debug, Default)]
+pub struct Engine {
+    rules: Vec<Rule>,
+}
+
+impl Engine {
+    pub fn new() -> Engine {
+        Engine::default()
+    }
+
+    /// The rules of `fizzbuzz` in section 01.
+    pub fn classic() -> Engine {
+        Engine::new()
+            .with(Rule::divisor(3, "fizz"))
+            .with(Rule::divisor(5, "buzz"))
+    }
+
+    pub fn with(mut self, rule: Rule) -> Engine {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Parses a rule file, reporting the first bad line.
+    pub fn from_rules(text: &str) -> Result<Engine, RuleError> {
+        let mut engine = Engine::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let rule = parse_rule(line).map_err(|message| RuleError {
+                line: index + 1,
+                message,
+            })?;
+            engine.rules.push(rule);
+        }
+        Ok(engine)
+    }
+
+    pub fn eval(&self, n: u64) -> String {
+        let words: String = self
+            .rules
+            .iter()
+            .filter(|rule| rule.matches(n))
+            .map(Rule::word)
+            .collect();
+        if words.is_empty() {
+            n.to_string()
+        } else {
+            words
+        }
+    }
+
+    /// Lazily evaluates every number in `range`.
+    pub fn iter(&self, range: RangeInclusive<u64>) -> impl Iterator<Item = String> + '_ {
+        range.map(move |n| self.eval(n))
+    }
+
+    /// Evaluates `range` on up to `threads` threads, keeping the output in
+    /// order. No more threads are used than the machine can run at once, or
+    /// than there are numbers.
+    pub fn par_eval(&self, range: RangeInclusive<u64>, threads: usize) -> Vec<String> {
+        let (start, end) = range.into_inner();
+        if start > end {
+            return Vec::new();
+        }
+        // in `u128` so that `0..=u64::MAX` does not overflow.
+        let len = (end - start) as u128 + 1;
+        let available = thread::available_parallelism().map_or(1, |n| n.get());
+        let threads = (threads.min(available).max(1) as u128).min(len);
+        let chunk = len.div_ceil(threads);
+        let chunks = len.div_ceil(chunk);
+
+        thread::scope(|s| {
+            let handles: Vec<_> = (0..chunks)
+                .map(|i| {
+                    let lo = start as u128 + i * chunk;
+                    let hi = (lo + chunk - 1).min(end as u128);
+                    s.spawn(move || self.iter(lo as u64..=hi as u64).collect::<Vec<String>>())
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the original implementation from section 01, returning instead of printing.
+    fn reference_fizzbuzz(n: u64) -> String {
+        if is_divisible_by(n, 15) {
+            "fizzbuzz".to_string()
+        } else if is_divisible_by(n, 3) {
+            "fizz".to_string()
+        } else if is_divisible_by(n, 5) {
+            "buzz".to_string()
+        } else {
+            n.to_string()
+        }
+    }
+
+    #[test]
+    fn classic_matches_the_original_for_1_to_100() {
+        let engine = Engine::classic();
+        let output: Vec<String> = engine.iter(1..=100).collect();
+        assert_eq!(output.len(), 100);
+        for (n, line) in (1..=100).zip(&output) {
+            assert_eq!(*line, reference_fizzbuzz(n));
+        }
+        assert_eq!(output[..5], ["1", "2", "fizz", "4", "buzz"]);
+        assert_eq!(output[14], "fizzbuzz");
+    }
+
+    #[test]
+    fn classic_properties() {
+        let engine = Engine::classic();
+        for n in 1..=10_000u64 {
+            let out = engine.eval(n);
+            assert_eq!(out.starts_with("fizz"), n % 3 == 0, "{}", n);
+            assert_eq!(out.ends_with("buzz"), n % 5 == 0, "{}", n);
+            if n % 3 != 0 && n % 5 != 0 {
+                assert_eq!(out.parse::<u64>(), Ok(n));
+            }
+        }
+    }
+
+    #[test]
+    fn predicate_rules_and_order() {
+        let engine = Engine::new()
+            .with(Rule::predicate("even", "even", |n| n % 2 == 0))
+            .with(Rule::divisor(3, "three"));
+        assert_eq!(engine.eval(6), "eventhree");
+        assert_eq!(engine.eval(7), "7");
+        assert_eq!(Engine::new().with(Rule::divisor(0, "never")).eval(0), "0");
+    }
+
+    #[test]
+    fn parses_rule_files() {
+        let text =
+            "# variant\n3 fizz\n5 buzz  # classic\n\ncontains 3 fizz\nprime zap\nsquare sq\n";
+        let engine = Engine::from_rules(text).unwrap();
+        assert_eq!(engine.rules().len(), 5);
+        assert_eq!(engine.eval(13), "fizzzap");
+        assert_eq!(engine.eval(33), "fizzfizz");
+        assert_eq!(engine.eval(25), "buzzsq");
+        assert_eq!(engine.eval(1), "sq");
+        assert_eq!(engine.eval(8), "8");
+    }
+
+    #[test]
+    fn reports_bad_rule_lines() {
+        let err = Engine::from_rules("3 fizz\n0 never\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: divisor must not be zero");
+        assert_eq!(Engine::from_rules("contains 12 x").unwrap_err().line, 1);
+        assert!(Engine::from_rules("cube x").is_err());
+        assert!(Engine::from_rules("3 fizz extra").is_err());
+    }
+
+    #[test]
+    fn parallel_output_is_ordered() {
+        let engine = Engine::from_rules("3 fizz\n5 buzz\nprime p").unwrap();
+        let sequential: Vec<String> = engine.iter(1..=10_007).collect();
+        for threads in [0, 1, 3, 8, 20_000] {
+            assert_eq!(engine.par_eval(1..=10_007, threads), sequential);
+        }
+        let (lo, hi) = (5, 4);
+        assert!(engine.par_eval(lo..=hi, 4).is_empty());
+
+        let classic = Engine::classic();
+        let top = classic.par_eval(u64::MAX - 2..=u64::MAX, 4);
+        assert_eq!(
+            top,
+            classic.iter(u64::MAX - 2..=u64::MAX).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn helpers() {
+        let primes: Vec<u64> = (0..30).filter(|&n| is_prime(n)).collect();
+        assert_eq!(primes, [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        let squares: Vec<u64> = (0..50).filter(|&n| is_square(n)).collect();
+        assert_eq!(squares, [0, 1, 4, 9, 16, 25, 36, 49]);
+        assert!(is_square(u32::MAX as u64 * u32::MAX as u64));
+    }
+}
@@ -0,0 +1,396 @@
+// Geometry
+//
+// `Point` and `Rectangle` from section 02, made safe to build from any two
+// corners: a `Rectangle` is always stored normalised, `min` holding the
+// smallest coordinates and `max` the largest, so widths, heights and areas are
+// never negative. Rectangles are closed: points on the edge are inside.
+//
+// `Quadtree` stores rectangles with a payload and answers region queries
+// without looking at every rectangle.
+
+use std::error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn origin() -> Point {
+        Point { x: 0.0, y: 0.0 }
+    }
+
+    pub fn new(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeometryError {
+    /// A coordinate was infinite or `NaN`.
+    NotFinite(Point),
+}
+
+impl fmt::Display for GeometryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GeometryError::NotFinite(p) => {
+                write!(f, "point ({}, {}) has a non-finite coordinate", p.x, p.y)
+            }
+        }
+    }
+}
+
+impl error::Error for GeometryError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rectangle {
+    min: Point,
+    max: Point,
+}
+
+impl Rectangle {
+    /// Builds the rectangle spanned by two opposite corners, in any order.
+    pub fn new(p1: Point, p2: Point) -> Result<Rectangle, GeometryError> {
+        for p in [p1, p2] {
+            if !p.is_finite() {
+                return Err(GeometryError::NotFinite(p));
+            }
+        }
+        Ok(Rectangle {
+            min: Point::new(p1.x.min(p2.x), p1.y.min(p2.y)),
+            max: Point::new(p1.x.max(p2.x), p1.y.max(p2.y)),
+        })
+    }
+
+    /// The rectangle with one corner at `origin`; negative sizes extend left/up.
+    pub fn with_size(origin: Point, width: f64, height: f64) -> Result<Rectangle, GeometryError> {
+        Rectangle::new(origin, Point::new(origin.x + width, origin.y + height))
+    }
+
+    pub fn min(&self) -> Point {
+        self.min
+    }
+
+    pub fn max(&self) -> Point {
+        self.max
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max.y - self.min.y
+    }
+
+    pub fn area(&self) -> f64 {
+        self.width() * self.height()
+    }
+
+    pub fn perimeter(&self) -> f64 {
+        2.0 * (self.width() + self.height())
+    }
+
+    pub fn center(&self) -> Point {
+        Point::new(
+            (self.min.x + self.max.x) / 2.0,
+            (self.min.y + self.max.y) / 2.0,
+        )
+    }
+
+    pub fn translate(&mut self, x: f64, y: f64) {
+        self.min.x += x;
+        self.min.y += y;
+        self.max.x += x;
+        self.max.y += y;
+    }
+
+    pub fn contains_point(&self, p: Point) -> bool {
+        self.min.x <= p.x && p.x <= self.max.x && self.min.y <= p.y && p.y <= self.max.y
+    }
+
+    /// `true` if `other` lies entirely inside `self`.
+    pub fn contains(&self, other: &Rectangle) -> bool {
+        self.contains_point(other.min) && self.contains_point(other.max)
+    }
+
+    /// `true` if the rectangles share at least a point, an edge counts.
+    pub fn touches(&self, other: &Rectangle) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+
+    /// `true` if the rectangles share some area; touching edges do not count.
+    pub fn overlaps(&self, other: &Rectangle) -> bool {
+        self.min.x < other.max.x
+            && other.min.x < self.max.x
+            && self.min.y < other.max.y
+            && other.min.y < self.max.y
+    }
+
+    /// The common part, `None` if the rectangles don't touch. Rectangles that
+    /// only share an edge give a rectangle with zero area.
+    pub fn intersection(&self, other: &Rectangle) -> Option<Rectangle> {
+        if !self.touches(other) {
+            return None;
+        }
+        Some(Rectangle {
+            min: Point::new(self.min.x.max(other.min.x), self.min.y.max(other.min.y)),
+            max: Point::new(self.max.x.min(other.max.x), self.max.y.min(other.max.y)),
+        })
+    }
+
+    /// The smallest rectangle containing both.
+    pub fn union(&self, other: &Rectangle) -> Rectangle {
+        Rectangle {
+            min: Point::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            max: Point::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        }
+    }
+
+    /// Integer points inside the rectangle, row by row.
+    pub fn grid_points(&self) -> impl Iterator<Item = (i64, i64)> {
+        let (x0, x1) = (self.min.x.ceil() as i64, self.max.x.floor() as i64);
+        let (y0, y1) = (self.min.y.ceil() as i64, self.max.y.floor() as i64);
+        (y0..=y1).flat_map(move |y| (x0..=x1).map(move |x| (x, y)))
+    }
+
+    // the four equal quarters, used by the quadtree.
+    fn quarters(&self) -> [Rectangle; 4] {
+        let c = self.center();
+        let (min, max) = (self.min, self.max);
+        [
+            Rectangle { min, max: c },
+            Rectangle {
+                min: Point::new(c.x, min.y),
+                max: Point::new(max.x, c.y),
+            },
+            Rectangle {
+                min: Point::new(min.x, c.y),
+                max: Point::new(c.x, max.y),
+            },
+            Rectangle { min: c, max },
+        ]
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Quadtree
+
+const NODE_CAPACITY: usize = 8;
+const MAX_DEPTH: usize = 10;
+
+#[derive(Debug)]
+struct Node<T> {
+    bounds: Rectangle,
+    // items that don't fit entirely in one child stay here.
+    items: Vec<(Rectangle, T)>,
+    children: Option<Box<[Node<T>; 4]>>,
+}
+
+impl<T> Node<T> {
+    fn new(bounds: Rectangle) -> Node<T> {
+        Node {
+            bounds,
+            items: Vec::new(),
+            children: None,
+        }
+    }
+
+    fn insert(&mut self, rect: Rectangle, value: T, depth: usize) {
+        if let Some(children) = &mut self.children {
+            if let Some(child) = children.iter_mut().find(|c| c.bounds.contains(&rect)) {
+                return child.insert(rect, value, depth + 1);
+            }
+        }
+        self.items.push((rect, value));
+        if self.children.is_none() && self.items.len() > NODE_CAPACITY && depth < MAX_DEPTH {
+            self.split(depth);
+        }
+    }
+
+    fn split(&mut self, depth: usize) {
+        self.children = Some(Box::new(self.bounds.quarters().map(Node::new)));
+        for (rect, value) in std::mem::take(&mut self.items) {
+            self.insert(rect, value, depth);
+        }
+    }
+
+    fn query<'a>(&'a self, region: &Rectangle, found: &mut Vec<(&'a Rectangle, &'a T)>) {
+        for (rect, value) in &self.items {
+            if rect.touches(region) {
+                found.push((rect, value));
+            }
+        }
+        if let Some(children) = &self.children {
+            for child in children.iter().filter(|c| c.bounds.touches(region)) {
+                child.query(region, found);
+            }
+        }
+    }
+}
+
+/// Rectangles with a payload, indexed for region queries. Rectangles that
+/// reach outside `bounds` are accepted but are checked on every query.
+#[derive(Debug)]
+pub struct Quadtree<T> {
+    root: Node<T>,
+    outside: Vec<(Rectangle, T)>,
+    len: usize,
+}
+
+impl<T> Quadtree<T> {
+    pub fn new(bounds: Rectangle) -> Quadtree<T> {
+        Quadtree {
+            root: Node::new(bounds),
+            outside: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn bounds(&self) -> Rectangle {
+        self.root.bounds
+    }
+
+    pub fn insert(&mut self, rect: Rectangle, value: T) {
+        if self.root.bounds.contains(&rect) {
+            self.root.insert(rect, value, 0);
+        } else {
+            self.outside.push((rect, value));
+        }
+        self.len += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Every stored rectangle touching `region`, in no particular order.
+    pub fn query(&self, region: &Rectangle) -> Vec<(&Rectangle, &T)> {
+        let mut found = Vec::new();
+        if self.root.bounds.touches(region) {
+            self.root.query(region, &mut found);
+        }
+        for (rect, value) in &self.outside {
+            if rect.touches(region) {
+                found.push((rect, value));
+            }
+        }
+        found
+    }
+
+    /// Every stored rectangle containing `p`.
+    pub fn query_point(&self, p: Point) -> Vec<(&Rectangle, &T)> {
+        self.query(&Rectangle { min: p, max: p })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x1: f64, y1: f64, x2: f64, y2: f64) -> Rectangle {
+        Rectangle::new(Point::new(x1, y1), Point::new(x2, y2)).unwrap()
+    }
+
+    #[test]
+    fn corners_in_any_order() {
+        let a = rect(3.0, 4.0, 0.0, 0.0);
+        assert_eq!(a, rect(0.0, 4.0, 3.0, 0.0));
+        assert_eq!(a.min(), Point::origin());
+        assert_eq!(a.area(), 12.0);
+        assert_eq!(a.perimeter(), 14.0);
+        assert_eq!(
+            Rectangle::with_size(Point::new(1.0, 1.0), -1.0, -1.0),
+            Ok(rect(0.0, 0.0, 1.0, 1.0))
+        );
+        assert!(Rectangle::new(Point::new(f64::NAN, 0.0), Point::origin()).is_err());
+    }
+
+    #[test]
+    fn set_operations() {
+        let a = rect(0.0, 0.0, 2.0, 2.0);
+        let b = rect(1.0, 1.0, 3.0, 3.0);
+        let c = rect(2.0, 0.0, 4.0, 1.0);
+        let far = rect(10.0, 10.0, 11.0, 11.0);
+
+        assert_eq!(a.intersection(&b), Some(rect(1.0, 1.0, 2.0, 2.0)));
+        assert_eq!(a.union(&b), rect(0.0, 0.0, 3.0, 3.0));
+        assert!(a.overlaps(&b));
+
+        // sharing an edge touches but does not overlap
+        assert!(a.touches(&c) && !a.overlaps(&c));
+        assert_eq!(a.intersection(&c).map(|r| r.area()), Some(0.0));
+        assert_eq!(a.intersection(&far), None);
+
+        assert!(a.union(&b).contains(&a));
+        assert!(!a.contains(&b));
+        assert!(a.contains_point(Point::new(2.0, 0.0)));
+    }
+
+    #[test]
+    fn translate_moves_both_corners() {
+        let mut square = rect(0.0, 0.0, 1.0, 1.0);
+        square.translate(1.0, 1.0);
+        assert_eq!(square, rect(1.0, 1.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn grid_points_are_inclusive() {
+        let points: Vec<(i64, i64)> = rect(-0.5, 0.0, 1.5, 1.0).grid_points().collect();
+        assert_eq!(points, [(0, 0), (1, 0), (0, 1), (1, 1)]);
+        assert_eq!(rect(0.2, 0.2, 0.8, 0.8).grid_points().count(), 0);
+    }
+
+    // a small xorshift generator for reproducible layouts.
+    fn boxes(count: usize) -> Vec<Rectangle> {
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        let mut next = move |limit: f64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % 10_000) as f64 / 10_000.0 * limit
+        };
+        (0..count)
+            .map(|_| {
+                let origin = Point::new(next(1000.0) - 10.0, next(1000.0) - 10.0);
+                Rectangle::with_size(origin, next(50.0), next(50.0)).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn quadtree_matches_brute_force() {
+        let rects = boxes(5000);
+        let mut tree = Quadtree::new(rect(0.0, 0.0, 1000.0, 1000.0));
+        for (i, r) in rects.iter().enumerate() {
+            tree.insert(*r, i);
+        }
+        assert_eq!(tree.len(), rects.len());
+
+        for region in boxes(50).iter().chain([&rect(-20.0, -20.0, 5.0, 5.0)]) {
+            let mut found: Vec<usize> = tree.query(region).into_iter().map(|(_, &i)| i).collect();
+            found.sort();
+            let expected: Vec<usize> = (0..rects.len())
+                .filter(|&i| rects[i].touches(region))
+                .collect();
+            assert_eq!(found, expected);
+        }
+
+        let p = rects[7].center();
+        assert!(tree.query_point(p).iter().any(|(_, &i)| i == 7));
+    }
+}
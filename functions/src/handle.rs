@@ -0,0 +1,220 @@
+// Must-consume handles
+//
+// `Pair::destroy(self)` in section 02 consumes its receiver, so the pair can't
+// be used afterwards. `Handle<R>` applies the same idea to resources whose
+// cleanup can fail: the only way to release it properly is `close(self)`, which
+// returns the cleanup error instead of swallowing it the way `Drop` has to.
+//
+// Dropping a handle that was never closed is a bug. Debug builds panic in
+// `drop` to report it; release builds make a best-effort close and ignore
+// the error.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::net::{Shutdown, TcpStream};
+use std::ops::{Deref, DerefMut};
+use std::thread;
+
+/// A resource with fallible cleanup.
+pub trait Close {
+    type Error;
+
+    fn close(self) -> Result<(), Self::Error>;
+}
+
+/// Flushes data and metadata to disk.
+impl Close for File {
+    type Error = io::Error;
+
+    fn close(self) -> io::Result<()> {
+        self.sync_all()
+    }
+}
+
+/// Flushes the buffer, then closes the inner writer. A failed final write is
+/// reported here instead of being lost in `BufWriter`'s `Drop`.
+impl<W> Close for BufWriter<W>
+where
+    W: Write + Close,
+    W::Error: From<io::Error>,
+{
+    type Error = W::Error;
+
+    fn close(self) -> Result<(), W::Error> {
+        let inner = self.into_inner().map_err(|e| e.into_error())?;
+        inner.close()
+    }
+}
+
+impl Close for TcpStream {
+    type Error = io::Error;
+
+    fn close(self) -> io::Result<()> {
+        self.shutdown(Shutdown::Both)
+    }
+}
+
+pub struct Handle<R: Close> {
+    name: String,
+    // `None` once the resource has been closed or taken out.
+    resource: Option<R>,
+}
+
+impl<R: Close> Handle<R> {
+    /// `name` identifies the handle in the leak report.
+    pub fn new(name: &str, resource: R) -> Handle<R> {
+        Handle {
+            name: name.to_string(),
+            resource: Some(resource),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Consumes the handle and releases the resource.
+    pub fn close(mut self) -> Result<(), R::Error> {
+        self.resource.take().unwrap().close()
+    }
+
+    /// Gives up the handle without closing; the caller takes over the duty.
+    pub fn into_inner(mut self) -> R {
+        self.resource.take().unwrap()
+    }
+}
+
+impl<R: Close> Deref for Handle<R> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        // only empty inside `close`/`into_inner`, which consume the handle.
+        self.resource.as_ref().unwrap()
+    }
+}
+
+impl<R: Close> DerefMut for Handle<R> {
+    fn deref_mut(&mut self) -> &mut R {
+        self.resource.as_mut().unwrap()
+    }
+}
+
+impl<R: Close> fmt::Debug for Handle<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Handle").field("name", &self.name).finish()
+    }
+}
+
+impl<R: Close> Drop for Handle<R> {
+    fn drop(&mut self) {
+        let Some(resource) = self.resource.take() else {
+            return;
+        };
+        // while unwinding the leak is a symptom, and a second panic would abort.
+        if cfg!(debug_assertions) && !thread::panicking() {
+            panic!("handle `{}` was dropped without calling `close`", self.name);
+        }
+        let _ = resource.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // `Pair` from section 02, recording its cleanup.
+    struct Pair(Box<i32>, Box<i32>, Rc<RefCell<Vec<String>>>);
+
+    impl Close for Pair {
+        type Error = String;
+
+        fn close(self) -> Result<(), String> {
+            let Pair(first, second, log) = self;
+            if *first < 0 {
+                return Err(format!("can't destroy pair({}, {})", first, second));
+            }
+            log.borrow_mut()
+                .push(format!("destroying pair({}, {})", first, second));
+            Ok(())
+        }
+    }
+
+    fn pair(a: i32, b: i32, log: &Rc<RefCell<Vec<String>>>) -> Handle<Pair> {
+        Handle::new("pair", Pair(Box::new(a), Box::new(b), Rc::clone(log)))
+    }
+
+    #[test]
+    fn close_consumes_and_reports_errors() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut handle = pair(1, 2, &log);
+        *handle.0 += 10;
+        assert_eq!(handle.close(), Ok(()));
+        // handle.close(); // ERROR: `handle` was moved by the first `close`
+        assert_eq!(*log.borrow(), ["destroying pair(11, 2)"]);
+
+        assert_eq!(
+            pair(-1, 2, &log).close(),
+            Err("can't destroy pair(-1, 2)".to_string())
+        );
+    }
+
+    #[test]
+    fn into_inner_disarms_the_check() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let inner = pair(3, 4, &log).into_inner();
+        assert_eq!(*inner.1, 4);
+        assert!(log.borrow().is_empty());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "handle `pair` was dropped without calling `close`")]
+    fn leaked_handles_panic_in_debug_builds() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let _leaked = pair(1, 2, &log);
+    }
+
+    #[test]
+    fn closes_buffered_files() -> io::Result<()> {
+        let path = std::env::temp_dir().join(format!("handle-{}.txt", std::process::id()));
+        let mut file = Handle::new("file", BufWriter::new(File::create(&path)?));
+        writeln!(file, "written through the handle")?;
+        file.close()?;
+        let contents = std::fs::read_to_string(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(contents, "written through the handle\n");
+        Ok(())
+    }
+
+    // A writer whose cleanup always fails.
+    struct FailsToClose(Vec<u8>);
+
+    impl Write for FailsToClose {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Close for FailsToClose {
+        type Error = io::Error;
+
+        fn close(self) -> io::Result<()> {
+            Err(io::Error::other(format!("lost {} bytes", self.0.len())))
+        }
+    }
+
+    #[test]
+    fn buffered_close_reports_the_inner_error() {
+        let mut writer = Handle::new("writer", BufWriter::new(FailsToClose(Vec::new())));
+        write!(writer, "abc").unwrap();
+        let err = writer.close().unwrap_err();
+        assert_eq!(err.to_string(), "lost 3 bytes");
+    }
+}
@@ -0,0 +1,8 @@
+// Functions library
+//
+// Reusable versions of the examples in `main.rs`.
+
+pub mod callback;
+pub mod fizzbuzz;
+pub mod geometry;
+pub mod handle;
@@ -2,33 +2,21 @@
 // section 01. overview
 
 /*
+use functions::fizzbuzz::{Engine, Rule};
+
 fn main() {
     fizzbuzz_to(100);
-}
-
-fn is_divisible_by(lhs: u32, rhs: u32) -> bool {
-    if rhs == 0 {
-        return false;
-    }
 
-    lhs % rhs == 0
-}
-
-fn fizzbuzz(n: u32) {
-    if is_divisible_by(n, 15) {
-        println!("fizzbuzz");
-    } else if is_divisible_by(n, 3) {
-        println!("fizz");
-    } else if is_divisible_by(n, 5) {
-        println!("buzz");
-    } else {
-        println!("{}", n);
-    }
+    // the rules are data, so variants don't need new code
+    let variant = Engine::classic().with(Rule::predicate("seven", "bang", |n| n % 7 == 0));
+    println!("{}", variant.eval(105));
 }
 
-fn fizzbuzz_to(n: u32) {
-    for n in 1..=n {
-        fizzbuzz(n);
+// the rules that used to be hardcoded in `fizzbuzz` are `Engine::classic()`,
+// and `iter` yields the lines instead of printing them.
+fn fizzbuzz_to(n: u64) {
+    for line in Engine::classic().iter(1..=n) {
+        println!("{}", line);
     }
 }
 */
@@ -0,0 +1,155 @@
+// Ages
+//
+// The new type idiom from section 09. `Years` and `Days` can only be built
+// through `TryFrom`, which rejects negative ages and ages too large to convert
+// between the two, so `to_days` can never overflow. Like units compare and add,
+// but a `Years` is never mixed up with a `Days`.
+
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::ops::Add;
+
+const DAYS_PER_YEAR: i64 = 365;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgeError {
+    Negative(i64),
+    TooLarge(i64),
+}
+
+impl fmt::Display for AgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AgeError::Negative(n) => write!(f, "an age can't be negative, got {}", n),
+            AgeError::TooLarge(n) => write!(f, "{} is too large for an age", n),
+        }
+    }
+}
+
+impl error::Error for AgeError {}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Years(i64);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Days(i64);
+
+impl Years {
+    pub const MAX: Years = Years(i64::MAX / DAYS_PER_YEAR);
+
+    pub fn to_days(self) -> Days {
+        Days(self.0 * DAYS_PER_YEAR)
+    }
+
+    pub fn checked_add(self, rhs: Years) -> Option<Years> {
+        self.0
+            .checked_add(rhs.0)
+            .and_then(|n| Years::try_from(n).ok())
+    }
+}
+
+impl Days {
+    pub const MAX: Days = Days(Years::MAX.0 * DAYS_PER_YEAR);
+
+    /// The number of whole years.
+    pub fn to_years(self) -> Years {
+        Years(self.0 / DAYS_PER_YEAR)
+    }
+
+    pub fn checked_add(self, rhs: Days) -> Option<Days> {
+        self.0
+            .checked_add(rhs.0)
+            .and_then(|n| Days::try_from(n).ok())
+    }
+}
+
+macro_rules! age {
+    ($($t:ident),*) => {$(
+        impl TryFrom<i64> for $t {
+            type Error = AgeError;
+
+            fn try_from(n: i64) -> Result<$t, AgeError> {
+                if n < 0 {
+                    Err(AgeError::Negative(n))
+                } else if n > $t::MAX.0 {
+                    Err(AgeError::TooLarge(n))
+                } else {
+                    Ok($t(n))
+                }
+            }
+        }
+
+        impl From<$t> for i64 {
+            fn from(age: $t) -> i64 {
+                age.0
+            }
+        }
+
+        /// # Panics
+        ///
+        /// Panics if the sum is larger than `MAX`.
+        impl Add for $t {
+            type Output = $t;
+
+            fn add(self, rhs: $t) -> $t {
+                self.checked_add(rhs).expect("age overflow")
+            }
+        }
+    )*};
+}
+
+age!(Years, Days);
+
+impl fmt::Display for Years {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} years", self.0)
+    }
+}
+
+impl fmt::Display for Days {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} days", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates() {
+        assert_eq!(Years::try_from(-1), Err(AgeError::Negative(-1)));
+        assert_eq!(Days::try_from(i64::MAX), Err(AgeError::TooLarge(i64::MAX)));
+        assert_eq!(Years::try_from(i64::MAX / 365), Ok(Years::MAX));
+        assert_eq!(Years::MAX.to_days(), Days::MAX);
+        assert_eq!(i64::from(Years::try_from(42).unwrap()), 42);
+    }
+
+    #[test]
+    fn adds_and_compares() {
+        let five = Years::try_from(5).unwrap();
+        let thirteen = Years::try_from(13).unwrap();
+        let eighteen = Years::try_from(18).unwrap();
+        assert_eq!(five + thirteen, eighteen);
+        assert!(five < eighteen);
+        assert_eq!(Years::MAX.checked_add(five), None);
+        assert_eq!(Years::MAX.checked_add(Years::MAX), None);
+        assert_eq!(Days::MAX.checked_add(Days::MAX), None);
+        let days = Days::try_from(400).unwrap();
+        assert_eq!((days + days).to_years(), Years::try_from(2).unwrap());
+        assert_eq!(eighteen.to_days().to_string(), "6570 days");
+    }
+
+    #[test]
+    #[should_panic(expected = "age overflow")]
+    fn add_panics_on_overflow() {
+        let _ = Days::MAX + Days::try_from(1).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "age overflow")]
+    fn add_panics_on_i64_overflow() {
+        let _ = Days::MAX + Days::MAX;
+    }
+}
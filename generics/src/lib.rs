@@ -0,0 +1,5 @@
+// Generics library
+//
+// Reusable versions of the examples in `main.rs`.
+
+pub mod age;
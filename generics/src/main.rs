@@ -364,40 +364,34 @@ fn main() {
 // ------------------------------------------------------------------
 // section 09. new type idiom
 
-/*
-
-struct Years(i64);
-struct Days(i64);
-
-impl Years {
-    pub fn to_days(&self) -> Days {
-        Days(self.0 * 365)
-    }
-}
+// `Years` and `Days` live in `generics::age`, where they also gain addition,
+// comparison and `TryFrom` validation: an age can't be negative.
 
-impl Days {
-    pub fn to_years(&self) -> Years {
-        Years(self.0 / 365)
-    }
-}
+/*
+use generics::age::{AgeError, Days, Years};
+use std::convert::TryFrom;
 
 fn old_enough(age: &Years) -> bool {
-    age.0 >= 18
+    *age >= Years::try_from(18).unwrap()
 }
 
-fn main() {
-    let age = Years(5);
+fn main() -> Result<(), AgeError> {
+    let age = Years::try_from(5)?;
 
     let age_days = age.to_days();
 
     println!("old enough {}", old_enough(&age));
     println!("old enough {}", old_enough(&age_days.to_years()));
     // println!("old enough {}", old_enough(&age_days)); // ERROR...
+    println!("old enough {}", old_enough(&(age + Years::try_from(13)?)));
+    // println!("{}", age + age_days); // ERROR.. mismatched types
+
+    println!("{:?}", Days::try_from(-1)); // Err(Negative(-1))
 
-    let years = Years(42);
-    let years_as_primitive_1 = years.0;
-    let Years(years_as_primitive_2) = years;
-    println!("{}, {}", years_as_primitive_1, years_as_primitive_2);
+    let years = Years::try_from(42)?;
+    let years_as_primitive = i64::from(years);
+    println!("{}, {}", years, years_as_primitive);
+    Ok(())
 }
 */
 
@@ -1,9 +1,25 @@
-#![crate_name = "doc"]
+//! People who can greet you.
+//!
+//! ```
+//! use doc::Person;
+//!
+//! let john = Person::new("John");
+//! john.hello();
+//! assert_eq!(john.greeting(), "Hello, John!");
+//!
+//! let jane = Person::builder("Jane").salutation("Good morning").build();
+//! assert_eq!(jane.greeting(), "Good morning, Jane!");
+//! ```
+
+use std::fmt;
 
 /// A human being is represented here
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Person {
     /// A person must have a name, no matter how much Juliet may hate it
     name: String,
+    salutation: String,
+    punctuation: char,
 }
 
 impl Person {
@@ -22,16 +38,94 @@ impl Person {
     /// let person = Person::new("name");
     /// ```
     pub fn new(name: &str) -> Person {
-        Person {
+        Person::builder(name).build()
+    }
+
+    /// Starts building a person whose greeting can be customised.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use doc::Person;
+    /// let person = Person::builder("Ferris")
+    ///     .salutation("Ahoy")
+    ///     .punctuation('.')
+    ///     .build();
+    /// assert_eq!(person.greeting(), "Ahoy, Ferris.");
+    /// ```
+    pub fn builder(name: &str) -> PersonBuilder {
+        PersonBuilder {
             name: name.to_string(),
+            salutation: "Hello".to_string(),
+            punctuation: '!',
         }
     }
 
+    /// The person's name.
+    ///
+    /// ```
+    /// assert_eq!(doc::Person::new("Juliet").name(), "Juliet");
+    /// ```
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The friendly hello, without printing it.
+    ///
+    /// ```
+    /// assert_eq!(doc::Person::new("Romeo").greeting(), "Hello, Romeo!");
+    /// ```
+    pub fn greeting(&self) -> String {
+        format!("{}, {}{}", self.salutation, self.name, self.punctuation)
+    }
+
     /// Gives a friendly hello!
     ///
     /// Says "Hello, [name](Person::name)" to the `Person` it is called on.
     pub fn hello(&self) {
-        println!("Hello, {}!", self.name);
+        println!("{}", self.greeting());
+    }
+}
+
+/// Displays the person's name.
+///
+/// ```
+/// let person = doc::Person::new("Juliet");
+/// assert_eq!(format!("Dear {}", person), "Dear Juliet");
+/// ```
+impl fmt::Display for Person {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Builds a [`Person`], see [`Person::builder`].
+#[derive(Debug, Clone)]
+pub struct PersonBuilder {
+    name: String,
+    salutation: String,
+    punctuation: char,
+}
+
+impl PersonBuilder {
+    /// The word the greeting starts with, `"Hello"` by default.
+    pub fn salutation(mut self, salutation: &str) -> PersonBuilder {
+        self.salutation = salutation.to_string();
+        self
+    }
+
+    /// The character the greeting ends with, `'!'` by default.
+    pub fn punctuation(mut self, punctuation: char) -> PersonBuilder {
+        self.punctuation = punctuation;
+        self
+    }
+
+    pub fn build(self) -> Person {
+        Person {
+            name: self.name,
+            salutation: self.salutation,
+            punctuation: self.punctuation,
+        }
     }
 }
 
@@ -41,11 +135,9 @@ pub use bar::Bar;
 /// bar docs
 mod bar {
     /// the docs for Bar
+    ///
+    /// ```
+    /// let _bar = doc::Bar;
+    /// ```
     pub struct Bar;
 }
-
-fn main() {
-    let john = Person::new("John");
-
-    john.hello();
-}
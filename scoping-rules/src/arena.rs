@@ -0,0 +1,360 @@
+// Arena-backed borrowed structures
+//
+// Generic versions of the lifetime structs from sections 13 and 14
+// (`Borrowed`, `NamedBorrowed`, `Either`) plus a typed arena. Values allocated
+// in an `Arena` live exactly as long as the arena, so `alloc` can hand out
+// plain `&'arena T` references and structures built from them (graphs with
+// cycles, ASTs that borrow from the source text) need neither `Rc` nor `unsafe`.
+
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+use std::ptr;
+
+/// A reference to a `T` that must outlive the `Borrowed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Borrowed<'a, T>(pub &'a T);
+
+/// Two references with the same lifetime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NamedBorrowed<'a, T> {
+    pub x: &'a T,
+    pub y: &'a T,
+}
+
+// --------------------------------------------------------------------------------
+// Either
+
+/// Either an owned `T` or a reference to one, like `std::borrow::Cow` for
+/// sized `Clone` types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Either<'a, T> {
+    Owned(T),
+    Ref(&'a T),
+}
+
+impl<'a, T> Either<'a, T> {
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self, Either::Ref(_))
+    }
+}
+
+impl<'a, T: Clone> Either<'a, T> {
+    /// An owned copy that no longer borrows anything.
+    pub fn to_owned<'b>(&self) -> Either<'b, T> {
+        Either::Owned(T::clone(self))
+    }
+
+    /// The owned value, cloning only if it was borrowed.
+    pub fn into_owned(self) -> T {
+        match self {
+            Either::Owned(value) => value,
+            Either::Ref(value) => value.clone(),
+        }
+    }
+
+    /// Mutable access, cloning first if the value was borrowed.
+    pub fn to_mut(&mut self) -> &mut T {
+        if let Either::Ref(value) = *self {
+            *self = Either::Owned(value.clone());
+        }
+        match self {
+            Either::Owned(value) => value,
+            Either::Ref(_) => unreachable!(),
+        }
+    }
+}
+
+impl<T> Deref for Either<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            Either::Owned(value) => value,
+            Either::Ref(value) => value,
+        }
+    }
+}
+
+impl<T> From<T> for Either<'_, T> {
+    fn from(value: T) -> Self {
+        Either::Owned(value)
+    }
+}
+
+impl<'a, T> From<&'a T> for Either<'a, T> {
+    fn from(value: &'a T) -> Self {
+        Either::Ref(value)
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Arena
+
+const FIRST_CHUNK: usize = 8;
+
+// Slots are filled once and never move: chunks are never reallocated, only
+// chained, each twice the size of the previous one.
+struct Chunk<T> {
+    slots: Box<[OnceCell<T>]>,
+    used: Cell<usize>,
+    next: OnceCell<Box<Chunk<T>>>,
+}
+
+impl<T> Chunk<T> {
+    fn with_capacity(capacity: usize) -> Chunk<T> {
+        Chunk {
+            slots: (0..capacity).map(|_| OnceCell::new()).collect(),
+            used: Cell::new(0),
+            next: OnceCell::new(),
+        }
+    }
+
+    fn filled(&self) -> &[OnceCell<T>] {
+        &self.slots[..self.used.get()]
+    }
+}
+
+/// A typed arena. Allocated values are dropped together with the arena.
+pub struct Arena<T> {
+    first: Chunk<T>,
+    len: Cell<usize>,
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Arena<T> {
+        Arena::new()
+    }
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Arena<T> {
+        Arena {
+            first: Chunk::with_capacity(FIRST_CHUNK),
+            len: Cell::new(0),
+        }
+    }
+
+    /// Moves `value` into the arena and returns a reference that lives as long
+    /// as the arena does.
+    pub fn alloc(&self, value: T) -> &T {
+        let mut chunk = &self.first;
+        loop {
+            let used = chunk.used.get();
+            if used < chunk.slots.len() {
+                chunk.used.set(used + 1);
+                self.len.set(self.len.get() + 1);
+                return chunk.slots[used].get_or_init(|| value);
+            }
+            chunk = chunk
+                .next
+                .get_or_init(|| Box::new(Chunk::with_capacity(chunk.slots.len() * 2)));
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Allocated values, in allocation order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        let chunks = std::iter::successors(Some(&self.first), |c| c.next.get().map(|b| &**b));
+        chunks.flat_map(|c| c.filled().iter().filter_map(OnceCell::get))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Arena<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Graph
+
+/// A graph node allocated in an arena. Edges are plain references to other
+/// nodes that live at least as long, so cycles are allowed.
+pub struct Node<'a, T> {
+    pub id: usize,
+    pub value: T,
+    edges: RefCell<Vec<&'a Node<'a, T>>>,
+}
+
+impl<'a, T> Node<'a, T> {
+    pub fn connect(&self, to: &'a Node<'a, T>) {
+        self.edges.borrow_mut().push(to);
+    }
+
+    pub fn edges(&self) -> Vec<&'a Node<'a, T>> {
+        self.edges.borrow().clone()
+    }
+}
+
+// edges are printed as ids, printing the nodes would recurse forever on a cycle.
+impl<T: fmt::Debug> fmt::Debug for Node<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let edges: Vec<usize> = self.edges.borrow().iter().map(|n| n.id).collect();
+        f.debug_struct("Node")
+            .field("id", &self.id)
+            .field("value", &self.value)
+            .field("edges", &edges)
+            .finish()
+    }
+}
+
+/// Builds nodes in an arena owned by the caller.
+pub struct Graph<'a, T> {
+    arena: &'a Arena<Node<'a, T>>,
+}
+
+impl<'a, T> Graph<'a, T> {
+    pub fn new(arena: &'a Arena<Node<'a, T>>) -> Graph<'a, T> {
+        Graph { arena }
+    }
+
+    pub fn add(&self, value: T) -> &'a Node<'a, T> {
+        self.arena.alloc(Node {
+            id: self.arena.len(),
+            value,
+            edges: RefCell::new(Vec::new()),
+        })
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &'a Node<'a, T>> {
+        self.arena.iter()
+    }
+
+    /// Nodes reachable from `start`, including itself, in breadth-first order.
+    /// Edges may lead into another arena with the same lifetime, whose ids
+    /// overlap with this one's, so nodes are told apart by address.
+    pub fn reachable(&self, start: &'a Node<'a, T>) -> Vec<&'a Node<'a, T>> {
+        let mut seen: HashSet<*const Node<'a, T>> = HashSet::from([ptr::from_ref(start)]);
+        let mut order = vec![start];
+        let mut next = 0;
+        while next < order.len() {
+            for &node in order[next].edges.borrow().iter() {
+                if seen.insert(ptr::from_ref(node)) {
+                    order.push(node);
+                }
+            }
+            next += 1;
+        }
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrowed_structs_are_generic() {
+        let (x, y) = (String::from("x"), String::from("y"));
+        let single = Borrowed(&x);
+        let double = NamedBorrowed { x: &x, y: &y };
+        assert_eq!(single.0, double.x);
+        assert_ne!(double.x, double.y);
+    }
+
+    #[test]
+    fn either_converts_between_borrowed_and_owned() {
+        let text = String::from("borrowed");
+        let mut value = Either::Ref(&text);
+        assert!(value.is_borrowed());
+        assert_eq!(value.len(), 8);
+
+        let owned: Either<String> = value.to_owned();
+        assert!(!owned.is_borrowed());
+        assert_eq!(owned, Either::Owned("borrowed".to_string()));
+
+        value.to_mut().push_str(" no more");
+        assert!(!value.is_borrowed());
+        assert_eq!(value.into_owned(), "borrowed no more");
+        assert_eq!(text, "borrowed");
+    }
+
+    #[test]
+    fn arena_references_stay_valid_across_chunks() {
+        let arena = Arena::new();
+        let refs: Vec<&usize> = (0..1000).map(|i| arena.alloc(i)).collect();
+        assert_eq!(arena.len(), 1000);
+        assert!(refs.iter().enumerate().all(|(i, r)| **r == i));
+        assert!(arena.iter().copied().eq(0..1000));
+    }
+
+    #[test]
+    fn graph_nodes_point_at_each_other() {
+        let arena = Arena::new();
+        let graph = Graph::new(&arena);
+        let a = graph.add("a");
+        let b = graph.add("b");
+        let c = graph.add("c");
+        let d = graph.add("d");
+        a.connect(b);
+        b.connect(c);
+        c.connect(a); // a cycle, no `Rc` or `Weak` needed
+        d.connect(a);
+
+        let names: Vec<&str> = graph.reachable(a).iter().map(|n| n.value).collect();
+        assert_eq!(names, ["a", "b", "c"]);
+        assert_eq!(graph.reachable(d).len(), 4);
+        assert_eq!(
+            format!("{:?}", c),
+            "Node { id: 2, value: \"c\", edges: [0] }"
+        );
+    }
+
+    #[test]
+    fn reachable_follows_edges_into_other_arenas() {
+        let (small, large) = (Arena::new(), Arena::new());
+        let (small, large) = (Graph::new(&small), Graph::new(&large));
+        let a = small.add("a");
+        let nodes: Vec<_> = ["w", "x", "y", "z"].map(|v| large.add(v)).into();
+        a.connect(nodes[3]);
+        nodes[3].connect(nodes[0]);
+        nodes[0].connect(a);
+
+        let names: Vec<&str> = small.reachable(a).iter().map(|n| n.value).collect();
+        assert_eq!(names, ["a", "z", "w"]);
+        assert_eq!(large.reachable(nodes[0]).len(), 3);
+    }
+
+    // A zero-copy AST: nodes live in the arena and numbers borrow the input.
+    #[derive(Debug)]
+    enum Expr<'a> {
+        Num(&'a str),
+        Add(&'a Expr<'a>, &'a Expr<'a>),
+        Mul(&'a Expr<'a>, &'a Expr<'a>),
+    }
+
+    fn parse<'a>(arena: &'a Arena<Expr<'a>>, input: &'a str) -> &'a Expr<'a> {
+        let sum = input.split('+').map(|term| {
+            let product = term.split('*').map(|n| arena.alloc(Expr::Num(n.trim())));
+            product.reduce(|l, r| arena.alloc(Expr::Mul(l, r))).unwrap()
+        });
+        sum.reduce(|l, r| arena.alloc(Expr::Add(l, r))).unwrap()
+    }
+
+    fn eval(expr: &Expr) -> i64 {
+        match expr {
+            Expr::Num(n) => n.parse().unwrap(),
+            Expr::Add(l, r) => eval(l) + eval(r),
+            Expr::Mul(l, r) => eval(l) * eval(r),
+        }
+    }
+
+    #[test]
+    fn arena_ast_borrows_the_source() {
+        let source = String::from("1 + 2 * 3 + 4");
+        let arena = Arena::new();
+        let ast = parse(&arena, &source);
+        assert_eq!(eval(ast), 11);
+        assert_eq!(arena.len(), 7);
+        assert!(matches!(arena.iter().next(), Some(Expr::Num("1"))));
+    }
+}
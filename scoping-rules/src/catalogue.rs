@@ -0,0 +1,462 @@
+// Library catalogue
+//
+// `Book` owns its strings and keeps every edition instead of overwriting the
+// year. Lending is enforced by the borrow checker: `Catalogue::checkout` returns
+// a `Loan` that mutably borrows the catalogue, so while any book is out nothing
+// can check the same book out again, edit it or remove it. Dropping the `Loan`
+// checks the book back in (RAII, as with `ToDrop` in section 02) and adds the
+// borrower to the book's loan history.
+//
+// The file format has one book per line, with tab separated fields:
+//
+//     author<TAB>title<TAB>year,year,...<TAB>borrower<TAB>borrower...
+//
+// Tabs, line breaks and backslashes inside fields are escaped as `\t`, `\n`,
+// `\r` and `\\`.
+
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::ops::Deref;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum CatalogueError {
+    UnknownBook(BookId),
+    /// The same book was requested twice in one `checkout_many`.
+    AlreadyRequested(BookId),
+    /// A new edition must not be older than the latest one.
+    EditionOutOfOrder {
+        latest: u32,
+        year: u32,
+    },
+    /// A line of a catalogue file could not be parsed.
+    Parse {
+        line: usize,
+        reason: &'static str,
+    },
+    Io(io::Error),
+}
+
+impl fmt::Display for CatalogueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CatalogueError::UnknownBook(id) => write!(f, "no book with id {}", id.0),
+            CatalogueError::AlreadyRequested(id) => {
+                write!(f, "book {} was requested more than once", id.0)
+            }
+            CatalogueError::EditionOutOfOrder { latest, year } => write!(
+                f,
+                "edition year {} is older than the latest edition ({})",
+                year, latest
+            ),
+            CatalogueError::Parse { line, reason } => write!(f, "line {}: {}", line, reason),
+            CatalogueError::Io(..) => write!(f, "could not access the catalogue file"),
+        }
+    }
+}
+
+impl error::Error for CatalogueError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            CatalogueError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CatalogueError {
+    fn from(err: io::Error) -> CatalogueError {
+        CatalogueError::Io(err)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Book {
+    author: String,
+    title: String,
+    // publication years, oldest first; never empty.
+    editions: Vec<u32>,
+    loans: Vec<String>,
+}
+
+impl Book {
+    pub fn new(author: &str, title: &str, year: u32) -> Book {
+        Book {
+            author: author.to_string(),
+            title: title.to_string(),
+            editions: vec![year],
+            loans: Vec::new(),
+        }
+    }
+
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// The year of the latest edition.
+    pub fn year(&self) -> u32 {
+        *self.editions.last().unwrap()
+    }
+
+    /// Publication years of every edition, oldest first.
+    pub fn editions(&self) -> &[u32] {
+        &self.editions
+    }
+
+    /// Adds an edition and returns its number, starting from 1.
+    pub fn new_edition(&mut self, year: u32) -> Result<usize, CatalogueError> {
+        let latest = self.year();
+        if year < latest {
+            return Err(CatalogueError::EditionOutOfOrder { latest, year });
+        }
+        self.editions.push(year);
+        Ok(self.editions.len())
+    }
+
+    /// Everyone who has borrowed and returned this book, in order.
+    pub fn loan_history(&self) -> &[String] {
+        &self.loans
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BookId(usize);
+
+#[derive(Debug, Default)]
+pub struct Catalogue {
+    books: Vec<Book>,
+}
+
+impl Catalogue {
+    pub fn new() -> Catalogue {
+        Catalogue::default()
+    }
+
+    pub fn add(&mut self, book: Book) -> BookId {
+        self.books.push(book);
+        BookId(self.books.len() - 1)
+    }
+
+    pub fn get(&self, id: BookId) -> Option<&Book> {
+        self.books.get(id.0)
+    }
+
+    pub fn get_mut(&mut self, id: BookId) -> Option<&mut Book> {
+        self.books.get_mut(id.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.books.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.books.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (BookId, &Book)> {
+        self.books.iter().enumerate().map(|(i, b)| (BookId(i), b))
+    }
+
+    /// Books whose author contains `query`, ignoring case.
+    pub fn search_author<'a>(&'a self, query: &str) -> impl Iterator<Item = (BookId, &'a Book)> {
+        let query = query.to_lowercase();
+        self.iter()
+            .filter(move |(_, b)| b.author.to_lowercase().contains(&query))
+    }
+
+    /// Books whose title contains `query`, ignoring case.
+    pub fn search_title<'a>(&'a self, query: &str) -> impl Iterator<Item = (BookId, &'a Book)> {
+        let query = query.to_lowercase();
+        self.iter()
+            .filter(move |(_, b)| b.title.to_lowercase().contains(&query))
+    }
+
+    /// Lends one book. The catalogue stays borrowed until the loan is dropped.
+    pub fn checkout(&mut self, id: BookId, borrower: &str) -> Result<Loan<'_>, CatalogueError> {
+        let book = self
+            .books
+            .get_mut(id.0)
+            .ok_or(CatalogueError::UnknownBook(id))?;
+        Ok(Loan::new(book, borrower))
+    }
+
+    /// Lends several different books at once, returned in the order requested.
+    pub fn checkout_many(
+        &mut self,
+        ids: &[BookId],
+        borrower: &str,
+    ) -> Result<Vec<Loan<'_>>, CatalogueError> {
+        let mut sorted = ids.to_vec();
+        sorted.sort();
+        for pair in sorted.windows(2) {
+            if pair[0] == pair[1] {
+                return Err(CatalogueError::AlreadyRequested(pair[0]));
+            }
+        }
+        if let Some(&last) = sorted.last() {
+            if last.0 >= self.books.len() {
+                return Err(CatalogueError::UnknownBook(last));
+            }
+        }
+
+        // hand out disjoint `&mut Book`s, then put them back in request order.
+        let mut loans: Vec<(BookId, Loan)> = self
+            .books
+            .iter_mut()
+            .enumerate()
+            .filter(|(i, _)| sorted.binary_search(&BookId(*i)).is_ok())
+            .map(|(i, book)| (BookId(i), Loan::new(book, borrower)))
+            .collect();
+        let mut ordered = Vec::with_capacity(ids.len());
+        for id in ids {
+            let index = loans.iter().position(|(i, _)| i == id).unwrap();
+            ordered.push(loans.swap_remove(index).1);
+        }
+        Ok(ordered)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), CatalogueError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Catalogue, CatalogueError> {
+        Catalogue::read_from(BufReader::new(File::open(path)?))
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for book in &self.books {
+            let years: Vec<String> = book.editions.iter().map(|y| y.to_string()).collect();
+            write!(
+                writer,
+                "{}\t{}\t{}",
+                escape(&book.author),
+                escape(&book.title),
+                years.join(",")
+            )?;
+            for borrower in &book.loans {
+                write!(writer, "\t{}", escape(borrower))?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: BufRead>(reader: R) -> Result<Catalogue, CatalogueError> {
+        let mut catalogue = Catalogue::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let parse_error = |reason| CatalogueError::Parse {
+                line: index + 1,
+                reason,
+            };
+
+            let fields = line
+                .split('\t')
+                .map(unescape)
+                .collect::<Option<Vec<String>>>()
+                .ok_or_else(|| parse_error("invalid escape sequence"))?;
+            let [author, title, years, loans @ ..] = fields.as_slice() else {
+                return Err(parse_error("expected author, title and years"));
+            };
+            let editions = years
+                .split(',')
+                .map(|y| y.parse::<u32>())
+                .collect::<Result<Vec<u32>, _>>()
+                .map_err(|_| parse_error("invalid edition year"))?;
+            if editions.windows(2).any(|pair| pair[0] > pair[1]) {
+                return Err(parse_error("edition years are out of order"));
+            }
+
+            catalogue.add(Book {
+                author: author.clone(),
+                title: title.clone(),
+                editions,
+                loans: loans.to_vec(),
+            });
+        }
+        Ok(catalogue)
+    }
+}
+
+/// A book on loan. Reading the book goes through `Deref`; dropping the loan
+/// returns it to the catalogue.
+#[derive(Debug)]
+pub struct Loan<'a> {
+    book: &'a mut Book,
+    borrower: String,
+}
+
+impl<'a> Loan<'a> {
+    fn new(book: &'a mut Book, borrower: &str) -> Loan<'a> {
+        Loan {
+            book,
+            borrower: borrower.to_string(),
+        }
+    }
+
+    pub fn borrower(&self) -> &str {
+        &self.borrower
+    }
+}
+
+impl Deref for Loan<'_> {
+    type Target = Book;
+
+    fn deref(&self) -> &Book {
+        self.book
+    }
+}
+
+impl Drop for Loan<'_> {
+    fn drop(&mut self) {
+        let borrower = std::mem::take(&mut self.borrower);
+        self.book.loans.push(borrower);
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(s: &str) -> Option<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => out.push('\\'),
+            't' => out.push('\t'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalogue() -> (Catalogue, BookId, BookId) {
+        let mut catalogue = Catalogue::new();
+        let geb = catalogue.add(Book::new("Douglas Hofstadter", "Gödel, Escher, Bach", 1979));
+        let loop_ = catalogue.add(Book::new("Douglas Hofstadter", "I Am a Strange Loop", 2007));
+        catalogue.add(Book::new("Ursula K. Le Guin", "The Dispossessed", 1974));
+        (catalogue, geb, loop_)
+    }
+
+    #[test]
+    fn editions_are_a_history() {
+        let mut book = Book::new("Douglas Hofstadter", "Gödel, Escher, Bach", 1979);
+        assert_eq!(book.new_edition(1999).unwrap(), 2);
+        assert_eq!(book.year(), 1999);
+        assert_eq!(book.editions(), [1979, 1999]);
+        assert!(matches!(
+            book.new_edition(1990),
+            Err(CatalogueError::EditionOutOfOrder {
+                latest: 1999,
+                year: 1990
+            })
+        ));
+    }
+
+    #[test]
+    fn dropping_a_loan_checks_the_book_in() {
+        let (mut catalogue, geb, _) = catalogue();
+        {
+            let loan = catalogue.checkout(geb, "alice").unwrap();
+            assert_eq!(loan.title(), "Gödel, Escher, Bach");
+            assert_eq!(loan.borrower(), "alice");
+            // catalogue.checkout(geb, "bob"); // ERROR: `catalogue` is still borrowed
+        }
+        catalogue.checkout(geb, "bob").unwrap();
+        assert_eq!(catalogue.get(geb).unwrap().loan_history(), ["alice", "bob"]);
+    }
+
+    #[test]
+    fn checkout_many_lends_distinct_books() {
+        let (mut catalogue, geb, loop_) = catalogue();
+        let loans = catalogue.checkout_many(&[loop_, geb], "carol").unwrap();
+        assert_eq!(loans[0].title(), "I Am a Strange Loop");
+        assert_eq!(loans[1].title(), "Gödel, Escher, Bach");
+        drop(loans);
+
+        assert!(matches!(
+            catalogue.checkout_many(&[geb, geb], "dave"),
+            Err(CatalogueError::AlreadyRequested(id)) if id == geb
+        ));
+        assert!(matches!(
+            catalogue.checkout(BookId(42), "dave"),
+            Err(CatalogueError::UnknownBook(_))
+        ));
+        assert_eq!(catalogue.get(geb).unwrap().loan_history(), ["carol"]);
+    }
+
+    #[test]
+    fn search_by_author_and_title() {
+        let (catalogue, geb, loop_) = catalogue();
+        let ids: Vec<BookId> = catalogue
+            .search_author("hofstadter")
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(ids, [geb, loop_]);
+        assert_eq!(catalogue.search_title("LOOP").count(), 1);
+        assert_eq!(catalogue.search_title("missing").count(), 0);
+    }
+
+    #[test]
+    fn persists_to_lines() {
+        let (mut catalogue, geb, _) = catalogue();
+        catalogue.get_mut(geb).unwrap().new_edition(1999).unwrap();
+        catalogue.add(Book::new("Tab\tAuthor", "Back\\slash\nTitle", 2000));
+        // `lines` would strip a bare `\r` at the end of a line
+        let windows = catalogue.add(Book::new("Line\r\nEndings", "Title\r", 2001));
+        drop(catalogue.checkout(geb, "erin"));
+        drop(catalogue.checkout(windows, "mallory\r"));
+
+        let mut buffer = Vec::new();
+        catalogue.write_to(&mut buffer).unwrap();
+        let text = String::from_utf8(buffer.clone()).unwrap();
+        assert_eq!(text.lines().count(), 5);
+        assert!(text.starts_with("Douglas Hofstadter\tGödel, Escher, Bach\t1979,1999\terin\n"));
+
+        let loaded = Catalogue::read_from(buffer.as_slice()).unwrap();
+        assert!(loaded
+            .iter()
+            .map(|(_, b)| b)
+            .eq(catalogue.iter().map(|(_, b)| b)));
+
+        let err = Catalogue::read_from("a\tb\t1990,1980\n".as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "line 1: edition years are out of order");
+        assert!(Catalogue::read_from("only author\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn saves_and_loads_files() {
+        let (catalogue, _, _) = catalogue();
+        let path = std::env::temp_dir().join(format!("catalogue-{}.txt", std::process::id()));
+        catalogue.save(&path).unwrap();
+        let loaded = Catalogue::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.len(), 3);
+    }
+}
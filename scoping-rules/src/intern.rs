@@ -0,0 +1,224 @@
+// String interner
+//
+// Section 17 shows that string literals are `&'static str` because they live
+// in the binary. An interner gets the same lifetime for strings built at run
+// time: each distinct string is leaked once and every later request for it
+// returns the same `&'static str`, or a `Symbol` that compares as a `u32`.
+//
+// Leaked strings are never freed, so only intern values from a bounded set
+// such as config keys or identifiers.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{OnceLock, RwLock};
+
+/// A handle to an interned string. Symbols from the same interner are equal
+/// exactly when their strings are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stats {
+    /// Distinct strings stored.
+    pub symbols: usize,
+    /// Bytes of string data leaked for them.
+    pub bytes: usize,
+    /// Calls to `intern`/`intern_str`.
+    pub requests: usize,
+    /// Requests answered with an existing string.
+    pub hits: usize,
+    /// Bytes that would have been allocated without deduplication.
+    pub saved_bytes: usize,
+}
+
+#[derive(Default)]
+struct Table {
+    ids: HashMap<&'static str, Symbol>,
+    strings: Vec<&'static str>,
+    bytes: usize,
+}
+
+/// A thread-safe interner. Lookups of known strings only take a read lock.
+#[derive(Default)]
+pub struct Interner {
+    table: RwLock<Table>,
+    requests: AtomicUsize,
+    hits: AtomicUsize,
+    saved_bytes: AtomicUsize,
+}
+
+impl Interner {
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+
+    pub fn intern(&self, s: &str) -> Symbol {
+        self.intern_full(s).0
+    }
+
+    /// The shared `'static` copy of `s`.
+    pub fn intern_str(&self, s: &str) -> &'static str {
+        self.intern_full(s).1
+    }
+
+    fn intern_full(&self, s: &str) -> (Symbol, &'static str) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if let Some(found) = self.find(s) {
+            return self.hit(found);
+        }
+
+        let mut table = self.table.write().unwrap();
+        // another thread may have added it between the two locks.
+        if let Some((&stored, &symbol)) = table.ids.get_key_value(s) {
+            drop(table);
+            return self.hit((symbol, stored));
+        }
+        let symbol = Symbol(u32::try_from(table.strings.len()).expect("too many symbols"));
+        let stored: &'static str = Box::leak(s.to_owned().into_boxed_str());
+        table.ids.insert(stored, symbol);
+        table.strings.push(stored);
+        table.bytes += stored.len();
+        (symbol, stored)
+    }
+
+    fn find(&self, s: &str) -> Option<(Symbol, &'static str)> {
+        let table = self.table.read().unwrap();
+        table
+            .ids
+            .get_key_value(s)
+            .map(|(&stored, &symbol)| (symbol, stored))
+    }
+
+    fn hit(&self, found: (Symbol, &'static str)) -> (Symbol, &'static str) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        self.saved_bytes.fetch_add(found.1.len(), Ordering::Relaxed);
+        found
+    }
+
+    /// The symbol for `s` if it was interned before; never allocates.
+    pub fn get(&self, s: &str) -> Option<Symbol> {
+        self.find(s).map(|(symbol, _)| symbol)
+    }
+
+    /// The string for a symbol from this interner. Symbols don't record which
+    /// interner made them, so one from another interner resolves to an
+    /// unrelated string, or `None` if its index is out of range.
+    pub fn resolve(&self, symbol: Symbol) -> Option<&'static str> {
+        self.table
+            .read()
+            .unwrap()
+            .strings
+            .get(symbol.index())
+            .copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.read().unwrap().strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> Stats {
+        let table = self.table.read().unwrap();
+        Stats {
+            symbols: table.strings.len(),
+            bytes: table.bytes,
+            requests: self.requests.load(Ordering::Relaxed),
+            hits: self.hits.load(Ordering::Relaxed),
+            saved_bytes: self.saved_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A process-wide interner, created on first use.
+pub fn global() -> &'static Interner {
+    static GLOBAL: OnceLock<Interner> = OnceLock::new();
+    GLOBAL.get_or_init(Interner::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn deduplicates_strings() {
+        let interner = Interner::new();
+        let a = interner.intern("config.key");
+        let b = interner.intern(&String::from("config.key"));
+        let c = interner.intern("other.key");
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(interner.len(), 2);
+
+        let s1 = interner.intern_str("config.key");
+        let s2 = interner.intern_str(&format!("config.{}", "key"));
+        assert!(std::ptr::eq(s1, s2));
+    }
+
+    #[test]
+    fn resolves_both_ways() {
+        let interner = Interner::new();
+        let symbol = interner.intern("ident");
+        assert_eq!(interner.resolve(symbol), Some("ident"));
+        assert_eq!(interner.get("ident"), Some(symbol));
+        assert_eq!(interner.get("missing"), None);
+        assert_eq!(interner.resolve(Symbol(99)), None);
+    }
+
+    #[test]
+    fn interned_strings_outlive_the_input() {
+        let stored: &'static str = {
+            let temporary = String::from("short lived");
+            global().intern_str(&temporary)
+        };
+        assert_eq!(stored, "short lived");
+    }
+
+    #[test]
+    fn counts_memory() {
+        let interner = Interner::new();
+        for _ in 0..3 {
+            interner.intern("abcd");
+        }
+        interner.intern("xy");
+        assert_eq!(
+            interner.stats(),
+            Stats {
+                symbols: 2,
+                bytes: 6,
+                requests: 4,
+                hits: 2,
+                saved_bytes: 8,
+            }
+        );
+    }
+
+    #[test]
+    fn threads_agree_on_symbols() {
+        let interner = Arc::new(Interner::new());
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let interner = Arc::clone(&interner);
+                thread::spawn(move || {
+                    (0..100)
+                        .map(|i| interner.intern(&format!("key{}", i)))
+                        .collect::<Vec<Symbol>>()
+                })
+            })
+            .collect();
+        let results: Vec<Vec<Symbol>> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert!(results.windows(2).all(|pair| pair[0] == pair[1]));
+        assert_eq!(interner.len(), 100);
+        assert_eq!(interner.stats().hits, 700);
+    }
+}
@@ -0,0 +1,8 @@
+// Scoping rules library
+//
+// Reusable versions of the examples in `main.rs`.
+
+pub mod arena;
+pub mod catalogue;
+pub mod intern;
+pub mod trace;
@@ -47,6 +47,8 @@ fn main() {
 //
 // Run the below example to see how the `Drop` trait works. When the variable in the main function
 // goes out of scope the custom destructor will be invoked.
+//
+// `scoping_rules::trace::Traced` records the same events into a log that tests can assert on.
 
 struct ToDrop;
 
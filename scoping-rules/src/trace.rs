@@ -0,0 +1,334 @@
+// Drop-order tracing
+//
+// `ToDrop` (section 02) and `Droppable` (traits, section 05) announce their
+// destruction with `println!`, which a test can't check. `Traced<T>` wraps a
+// value and records its construction, explicit moves, clones and drop in a
+// shared log instead. Every event gets a sequence number, so the order can be
+// asserted on, and the log is behind a `Mutex` so values may be traced across
+// threads.
+//
+//     let tracer = Tracer::new();
+//     let a = tracer.track("a", ());
+//     {
+//         let inner = tracer.scope("block");
+//         let _b = inner.track("b", ());
+//     }
+//     drop(a);
+//     tracer.assert_dropped_before("b", "a");
+//     tracer.assert_no_leaks();
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Created,
+    /// Recorded by `Traced::move_to`; plain moves are bitwise copies and can't be observed.
+    Moved,
+    Cloned,
+    Dropped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub seq: usize,
+    pub kind: EventKind,
+    pub name: String,
+    pub scope: String,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "#{} {:?} {} in {}",
+            self.seq, self.kind, self.name, self.scope
+        )
+    }
+}
+
+/// A handle to a shared event log, labelled with the scope new values are
+/// created in. Clones share the same log.
+#[derive(Debug, Clone)]
+pub struct Tracer {
+    events: Arc<Mutex<Vec<Event>>>,
+    scope: String,
+}
+
+impl Default for Tracer {
+    fn default() -> Tracer {
+        Tracer::new()
+    }
+}
+
+impl Tracer {
+    pub fn new() -> Tracer {
+        Tracer {
+            events: Arc::new(Mutex::new(Vec::new())),
+            scope: "root".to_string(),
+        }
+    }
+
+    /// A handle to the same log whose values are labelled with `label`,
+    /// nested inside this handle's scope as `outer/label`.
+    pub fn scope(&self, label: &str) -> Tracer {
+        Tracer {
+            events: Arc::clone(&self.events),
+            scope: format!("{}/{}", self.scope, label),
+        }
+    }
+
+    pub fn track<T>(&self, name: &str, value: T) -> Traced<T> {
+        let traced = Traced {
+            value,
+            name: name.to_string(),
+            tracer: self.clone(),
+        };
+        traced.record(EventKind::Created);
+        traced
+    }
+
+    fn log(&self) -> MutexGuard<'_, Vec<Event>> {
+        // a panicking test thread must not hide the log from the others.
+        self.events.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn push(&self, kind: EventKind, name: &str) {
+        let mut log = self.log();
+        let seq = log.len();
+        log.push(Event {
+            seq,
+            kind,
+            name: name.to_string(),
+            scope: self.scope.clone(),
+        });
+    }
+
+    /// A copy of every event recorded so far, in order.
+    pub fn events(&self) -> Vec<Event> {
+        self.log().clone()
+    }
+
+    fn position(&self, kind: EventKind, name: &str) -> Option<usize> {
+        self.log()
+            .iter()
+            .find(|e| e.kind == kind && e.name == name)
+            .map(|e| e.seq)
+    }
+
+    pub fn is_dropped(&self, name: &str) -> bool {
+        self.position(EventKind::Dropped, name).is_some()
+    }
+
+    /// Names of values that were created or cloned but not dropped yet.
+    pub fn alive(&self) -> Vec<String> {
+        let mut alive = Vec::new();
+        for event in self.log().iter() {
+            match event.kind {
+                EventKind::Created | EventKind::Cloned => alive.push(event.name.clone()),
+                EventKind::Dropped => {
+                    if let Some(i) = alive.iter().position(|n| *n == event.name) {
+                        alive.remove(i);
+                    }
+                }
+                EventKind::Moved => {}
+            }
+        }
+        alive
+    }
+
+    /// Panics unless both values were dropped and `first` was dropped before `second`.
+    pub fn assert_dropped_before(&self, first: &str, second: &str) {
+        let a = self.position(EventKind::Dropped, first);
+        let b = self.position(EventKind::Dropped, second);
+        match (a, b) {
+            (Some(a), Some(b)) if a < b => {}
+            _ => panic!(
+                "expected `{}` to be dropped before `{}`, log:\n{}",
+                first, second, self
+            ),
+        }
+    }
+
+    /// Panics if any traced value is still alive.
+    pub fn assert_no_leaks(&self) {
+        let alive = self.alive();
+        if !alive.is_empty() {
+            panic!("values were never dropped: {:?}, log:\n{}", alive, self);
+        }
+    }
+}
+
+/// Prints the log, one event per line.
+impl fmt::Display for Tracer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for event in self.log().iter() {
+            writeln!(f, "{}", event)?;
+        }
+        Ok(())
+    }
+}
+
+/// A value whose lifecycle is recorded by a `Tracer`. Access the value through
+/// `Deref`/`DerefMut`.
+#[derive(Debug)]
+pub struct Traced<T = ()> {
+    value: T,
+    name: String,
+    tracer: Tracer,
+}
+
+impl<T> Traced<T> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn record(&self, kind: EventKind) {
+        self.tracer.push(kind, &self.name);
+    }
+
+    /// Moves the value into the scope of `tracer` and records the move.
+    pub fn move_to(mut self, tracer: &Tracer) -> Traced<T> {
+        self.tracer = tracer.clone();
+        self.record(EventKind::Moved);
+        self
+    }
+}
+
+/// The clone is named after the original with a `'` appended, `a` -> `a'`.
+impl<T: Clone> Clone for Traced<T> {
+    fn clone(&self) -> Traced<T> {
+        let clone = Traced {
+            value: self.value.clone(),
+            name: format!("{}'", self.name),
+            tracer: self.tracer.clone(),
+        };
+        clone.record(EventKind::Cloned);
+        clone
+    }
+}
+
+impl<T> Deref for Traced<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Traced<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T> Drop for Traced<T> {
+    fn drop(&mut self) {
+        self.record(EventKind::Dropped);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn nested_blocks_drop_inner_first() {
+        // the `Droppable` example from traits, section 05
+        let tracer = Tracer::new();
+        let a = tracer.track("a", ());
+        {
+            let block_a = tracer.scope("A");
+            let _b = block_a.track("b", ());
+            {
+                let block_b = block_a.scope("B");
+                let _c = block_b.track("c", ());
+                let _d = block_b.track("d", ());
+            }
+            assert!(tracer.is_dropped("c"));
+            assert!(!tracer.is_dropped("b"));
+        }
+        drop(a);
+
+        // locals are dropped in reverse order of declaration
+        tracer.assert_dropped_before("d", "c");
+        tracer.assert_dropped_before("c", "b");
+        tracer.assert_dropped_before("b", "a");
+        tracer.assert_no_leaks();
+
+        let events = tracer.events();
+        assert_eq!(events[2].to_string(), "#2 Created c in root/A/B");
+    }
+
+    #[test]
+    fn records_clones_and_moves() {
+        let tracer = Tracer::new();
+        let mut x = tracer.track("x", vec![1, 2]);
+        x.push(3);
+        let y = x.clone();
+        assert_eq!(*y, [1, 2, 3]);
+
+        let inner = tracer.scope("inner");
+        let x = x.move_to(&inner);
+        drop(x);
+        assert_eq!(tracer.alive(), ["x'"]);
+        drop(y);
+
+        let kinds: Vec<(EventKind, String, String)> = tracer
+            .events()
+            .into_iter()
+            .map(|e| (e.kind, e.name, e.scope))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (EventKind::Created, "x".into(), "root".into()),
+                (EventKind::Cloned, "x'".into(), "root".into()),
+                (EventKind::Moved, "x".into(), "root/inner".into()),
+                (EventKind::Dropped, "x".into(), "root/inner".into()),
+                (EventKind::Dropped, "x'".into(), "root".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn sequence_numbers_are_shared_across_threads() {
+        let tracer = Tracer::new();
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let tracer = tracer.scope(&format!("thread {}", i));
+                thread::spawn(move || {
+                    let _value = tracer.track(&format!("v{}", i), i);
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let events = tracer.events();
+        assert_eq!(events.len(), 8);
+        assert!(events.iter().enumerate().all(|(i, e)| e.seq == i));
+        tracer.assert_no_leaks();
+    }
+
+    #[test]
+    #[should_panic(expected = "values were never dropped: [\"leaked\"]")]
+    fn reports_leaks() {
+        let tracer = Tracer::new();
+        std::mem::forget(tracer.track("leaked", ()));
+        tracer.assert_no_leaks();
+    }
+
+    #[test]
+    #[should_panic(expected = "expected `a` to be dropped before `b`")]
+    fn reports_wrong_order() {
+        let tracer = Tracer::new();
+        let a = tracer.track("a", ());
+        drop(tracer.track("b", ()));
+        drop(a);
+        tracer.assert_dropped_before("a", "b");
+    }
+}
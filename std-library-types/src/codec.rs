@@ -0,0 +1,423 @@
+// Codec
+//
+// Encoders and decoders for looking at raw bytes: hex, the escapes of a byte
+// string literal, base64, and a `hexdump -C` style dump that can be parsed
+// back. Every `to_*`/`from_*` pair round-trips.
+
+use std::error;
+use std::fmt;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodecError {
+    /// An unexpected character, at a byte offset in the input.
+    Invalid { position: usize, found: char },
+    /// The input ended in the middle of a byte or escape.
+    Truncated,
+    /// A hexdump line that isn't an offset followed by hex bytes.
+    BadLine { line: usize },
+    /// A hexdump line whose offset doesn't follow the previous lines.
+    BadOffset {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CodecError::Invalid { position, found } => {
+                write!(f, "unexpected {:?} at {}", found, position)
+            }
+            CodecError::Truncated => write!(f, "input ends in the middle of a value"),
+            CodecError::BadLine { line } => write!(f, "line {}: not a hexdump line", line),
+            CodecError::BadOffset {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: offset {:08x} doesn't follow {:08x}",
+                line, found, expected
+            ),
+        }
+    }
+}
+
+impl error::Error for CodecError {}
+
+/// Lowercase hex, two digits per byte.
+///
+///     use std_library_types::codec;
+///
+///     let bytes = b"\x52\x75\x73\x74 as bytes";
+///     assert_eq!(codec::to_hex(b"Rust"), "52757374");
+///     assert_eq!(codec::from_hex(&codec::to_hex(bytes)).unwrap(), bytes);
+pub fn to_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        let _ = write!(out, "{:02x}", b);
+    }
+    out
+}
+
+/// Decodes hex digits of either case. Whitespace between bytes is ignored, so
+/// `"de ad be ef"` works too.
+pub fn from_hex(text: &str) -> Result<Vec<u8>, CodecError> {
+    let mut bytes = Vec::with_capacity(text.len() / 2);
+    let mut high = None;
+    for (position, c) in text.char_indices() {
+        if c.is_ascii_whitespace() && high.is_none() {
+            continue;
+        }
+        let digit = c
+            .to_digit(16)
+            .ok_or(CodecError::Invalid { position, found: c })? as u8;
+        match high.take() {
+            None => high = Some(digit),
+            Some(h) => bytes.push(h << 4 | digit),
+        }
+    }
+    match high {
+        None => Ok(bytes),
+        Some(_) => Err(CodecError::Truncated),
+    }
+}
+
+/// The bytes as the body of a byte string literal: printable ASCII as is,
+/// `\n`, `\r`, `\t`, `\\`, `\'`, `\"`, and `\xNN` for everything else. `{:?}`
+/// on a `&[u8]` only prints a list of numbers; this is the form a `b"..."`
+/// literal needs, and what `{}` prints for `bytes.escape_ascii()`.
+///
+///     use std_library_types::codec;
+///
+///     assert_eq!(codec::escape_bytes(b"\xffok\n"), r"\xffok\n");
+pub fn escape_bytes(bytes: &[u8]) -> String {
+    bytes.escape_ascii().to_string()
+}
+
+/// Reverses `escape_bytes`. Also accepts `\0`, and hex digits of either case.
+pub fn unescape_bytes(text: &str) -> Result<Vec<u8>, CodecError> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut chars = text.char_indices();
+    while let Some((position, c)) = chars.next() {
+        if c != '\\' {
+            if !c.is_ascii() {
+                return Err(CodecError::Invalid { position, found: c });
+            }
+            bytes.push(c as u8);
+            continue;
+        }
+        let (position, escape) = chars.next().ok_or(CodecError::Truncated)?;
+        match escape {
+            'n' => bytes.push(b'\n'),
+            'r' => bytes.push(b'\r'),
+            't' => bytes.push(b'\t'),
+            '0' => bytes.push(b'\0'),
+            '\\' | '\'' | '"' => bytes.push(escape as u8),
+            'x' => {
+                let mut value = 0;
+                for _ in 0..2 {
+                    let (position, c) = chars.next().ok_or(CodecError::Truncated)?;
+                    let digit = c
+                        .to_digit(16)
+                        .ok_or(CodecError::Invalid { position, found: c })?;
+                    value = value << 4 | digit as u8;
+                }
+                bytes.push(value);
+            }
+            found => return Err(CodecError::Invalid { position, found }),
+        }
+    }
+    Ok(bytes)
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard base64 (RFC 4648) with `=` padding.
+///
+///     use std_library_types::codec;
+///
+///     assert_eq!(codec::to_base64(b"Rust"), "UnVzdA==");
+///     assert_eq!(codec::from_base64("UnVzdA").unwrap(), b"Rust");
+pub fn to_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (i, &b)| group | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decodes standard base64. Padding is optional, but if present it must be
+/// at the end and complete the last group. The bits of the last character
+/// that don't make up a whole byte must be zero, so that every encoding is
+/// canonical: `"Zg=="` is accepted and `"Zh=="` is not.
+pub fn from_base64(text: &str) -> Result<Vec<u8>, CodecError> {
+    let data = text.trim_end_matches('=');
+    let padding = text.len() - data.len();
+    if padding > 0 && (padding > 2 || !text.len().is_multiple_of(4)) {
+        return Err(CodecError::Invalid {
+            position: data.len(),
+            found: '=',
+        });
+    }
+    if data.len() % 4 == 1 {
+        return Err(CodecError::Truncated);
+    }
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+    let mut group = 0u32;
+    for (position, c) in data.char_indices() {
+        let value = BASE64
+            .iter()
+            .position(|&b| b as char == c)
+            .ok_or(CodecError::Invalid { position, found: c })?;
+        group = group << 6 | value as u32;
+        if position % 4 == 3 {
+            bytes.extend_from_slice(&group.to_be_bytes()[1..]);
+            group = 0;
+        }
+    }
+    // the leftover bits of the last group
+    let unused = match data.len() % 4 {
+        2 => 4,
+        3 => 2,
+        _ => 0,
+    };
+    if group & ((1 << unused) - 1) != 0 {
+        let (position, found) = data.char_indices().last().unwrap();
+        return Err(CodecError::Invalid { position, found });
+    }
+    match data.len() % 4 {
+        2 => bytes.push((group >> 4) as u8),
+        3 => bytes.extend_from_slice(&((group >> 2) as u16).to_be_bytes()),
+        _ => {}
+    }
+    Ok(bytes)
+}
+
+const BYTES_PER_LINE: usize = 16;
+
+/// A dump in the format of `hexdump -C`: the offset, sixteen bytes in hex in
+/// two groups of eight, and the printable ASCII between `|`s. A last line
+/// holds the total length. Unlike `hexdump`, repeated lines aren't collapsed
+/// into `*`.
+///
+///     let dump = std_library_types::codec::hexdump(b"this is a byte string");
+///     assert_eq!(
+///         dump,
+///         "00000000  74 68 69 73 20 69 73 20  61 20 62 79 74 65 20 73  |this is a byte s|\n\
+///          00000010  74 72 69 6e 67                                    |tring|\n\
+///          00000015\n"
+///     );
+pub fn hexdump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (i, line) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+        let _ = write!(out, "{:08x} ", i * BYTES_PER_LINE);
+        for j in 0..BYTES_PER_LINE {
+            if j % 8 == 0 {
+                out.push(' ');
+            }
+            match line.get(j) {
+                Some(b) => {
+                    let _ = write!(out, "{:02x} ", b);
+                }
+                None => out.push_str("   "),
+            }
+        }
+        out.push_str(" |");
+        out.extend(line.iter().map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        }));
+        out.push_str("|\n");
+    }
+    if !bytes.is_empty() {
+        let _ = writeln!(out, "{:08x}", bytes.len());
+    }
+    out
+}
+
+/// Parses the output of `hexdump`, or of `hexdump -C` without repeated
+/// lines. The ASCII column is ignored and the closing offset is optional, but
+/// every offset must follow on from the bytes before it.
+pub fn parse_hexdump(dump: &str) -> Result<Vec<u8>, CodecError> {
+    let mut bytes = Vec::new();
+    let mut ended = false;
+    for (i, line) in dump
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+    {
+        let line_number = i + 1;
+        let bad_line = CodecError::BadLine { line: line_number };
+        let line = line.split('|').next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        let offset = fields
+            .next()
+            .and_then(|f| usize::from_str_radix(f, 16).ok())
+            .ok_or(bad_line.clone())?;
+        if ended {
+            return Err(bad_line);
+        }
+        if offset != bytes.len() {
+            return Err(CodecError::BadOffset {
+                line: line_number,
+                expected: bytes.len(),
+                found: offset,
+            });
+        }
+        let start = bytes.len();
+        for field in fields {
+            let byte = Some(field)
+                .filter(|f| f.len() == 2)
+                .and_then(|f| u8::from_str_radix(f, 16).ok())
+                .ok_or(bad_line.clone())?;
+            bytes.push(byte);
+        }
+        ended = bytes.len() == start;
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every byte value, plus lengths that exercise the partial groups
+    fn samples() -> Vec<Vec<u8>> {
+        let mut samples: Vec<Vec<u8>> = (0..=5).map(|n| b"Rust!"[..n].to_vec()).collect();
+        samples.push((0..=255).collect());
+        samples.push(b"\x82\xe6\x82\xa8\x82\xb1\x82\xbb".to_vec());
+        samples
+    }
+
+    #[test]
+    fn round_trips() {
+        for bytes in samples() {
+            assert_eq!(from_hex(&to_hex(&bytes)).unwrap(), bytes);
+            assert_eq!(unescape_bytes(&escape_bytes(&bytes)).unwrap(), bytes);
+            assert_eq!(from_base64(&to_base64(&bytes)).unwrap(), bytes);
+            let unpadded = to_base64(&bytes).trim_end_matches('=').to_string();
+            assert_eq!(from_base64(&unpadded).unwrap(), bytes);
+            assert_eq!(parse_hexdump(&hexdump(&bytes)).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn decodes_hex() {
+        assert_eq!(from_hex("DE ad\nbeEF").unwrap(), [0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(from_hex("abc"), Err(CodecError::Truncated));
+        assert_eq!(
+            from_hex("a bc"),
+            Err(CodecError::Invalid {
+                position: 1,
+                found: ' '
+            })
+        );
+        assert_eq!(
+            from_hex("zz"),
+            Err(CodecError::Invalid {
+                position: 0,
+                found: 'z'
+            })
+        );
+    }
+
+    #[test]
+    fn escapes_like_byte_literals() {
+        assert_eq!(escape_bytes(b"\x52\x75\x73\x74 as bytes"), "Rust as bytes");
+        assert_eq!(escape_bytes(b"\0\x7f\"'\\"), r#"\x00\x7f\"\'\\"#);
+        assert_eq!(unescape_bytes(r"\0\xfF").unwrap(), [0, 0xff]);
+        assert_eq!(unescape_bytes(r"\x4"), Err(CodecError::Truncated));
+        assert_eq!(
+            unescape_bytes("ℝ"),
+            Err(CodecError::Invalid {
+                position: 0,
+                found: 'ℝ'
+            })
+        );
+        assert_eq!(
+            unescape_bytes(r"\u{52}"),
+            Err(CodecError::Invalid {
+                position: 1,
+                found: 'u'
+            })
+        );
+    }
+
+    #[test]
+    fn base64_matches_rfc_4648() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (plain, encoded) in vectors {
+            assert_eq!(to_base64(plain.as_bytes()), encoded);
+            assert_eq!(from_base64(encoded).unwrap(), plain.as_bytes());
+        }
+        assert_eq!(from_base64("Zm9vY"), Err(CodecError::Truncated));
+        assert!(from_base64("Zg=").is_err());
+        assert!(from_base64("Zg===").is_err());
+        assert!(from_base64("Z=g=").is_err());
+        assert!(from_base64("Zm9v!").is_err());
+        assert_eq!(
+            from_base64("Zh=="),
+            Err(CodecError::Invalid {
+                position: 1,
+                found: 'h'
+            })
+        );
+        assert!(from_base64("Zm9=").is_err());
+        assert_eq!(from_base64("Zm8=").unwrap(), b"fo");
+    }
+
+    #[test]
+    fn parses_hexdumps() {
+        assert_eq!(hexdump(b""), "");
+        let dump = "00000000  52 75 73 74 |Rust|\n00000004  21\n";
+        assert_eq!(parse_hexdump(dump).unwrap(), b"Rust!");
+        assert_eq!(
+            parse_hexdump("00000000  52\n00000002  75\n"),
+            Err(CodecError::BadOffset {
+                line: 2,
+                expected: 1,
+                found: 2
+            })
+        );
+        assert_eq!(
+            parse_hexdump("00000000  5\n"),
+            Err(CodecError::BadLine { line: 1 })
+        );
+        assert_eq!(
+            parse_hexdump("00000001\n00000001  52\n"),
+            Err(CodecError::BadOffset {
+                line: 1,
+                expected: 0,
+                found: 1
+            })
+        );
+        assert_eq!(
+            parse_hexdump("00000000\n00000000  52\n"),
+            Err(CodecError::BadLine { line: 2 })
+        );
+    }
+}
@@ -0,0 +1,361 @@
+// Concurrency
+//
+// Shared-state patterns for the `Arc` example of section 12, none of which
+// rely on `thread::sleep` to coordinate:
+//
+//   - `Workers` keeps the handles of the threads it spawns and joins them, so
+//     no thread outlives the set and panics are reported, not lost.
+//   - `Config` holds a value behind a `RwLock`. Readers take an `Arc`
+//     snapshot and keep a consistent view for as long as they like without
+//     holding the lock.
+//   - `Versioned` pairs a value with a version number that changes with it,
+//     and lets threads block on a condition variable until it changes.
+//
+// Updates take a function from the old value to the new one and only store
+// the result once it returns, so a panicking update leaves the old value in
+// place. Poisoned locks are therefore safe to keep using.
+
+use std::io;
+use std::panic;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// A set of threads that are all joined, by `join_all` or on drop. Dropping
+/// the set re-raises the panic of a thread that panicked.
+#[derive(Debug)]
+pub struct Workers<T> {
+    handles: Vec<JoinHandle<T>>,
+}
+
+impl<T> Default for Workers<T> {
+    fn default() -> Self {
+        Workers {
+            handles: Vec::new(),
+        }
+    }
+}
+
+impl<T: Send + 'static> Workers<T> {
+    pub fn new() -> Self {
+        Workers::default()
+    }
+
+    pub fn spawn<F>(&mut self, f: F)
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        self.handles.push(thread::spawn(f));
+    }
+
+    /// Spawns a thread with a name, which shows up in panic messages.
+    pub fn spawn_named<F>(&mut self, name: &str, f: F) -> io::Result<()>
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let handle = thread::Builder::new().name(name.to_string()).spawn(f)?;
+        self.handles.push(handle);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Waits for every thread, returning their results in the order they
+    /// were spawned. A thread that panicked gives `Err` with the payload.
+    pub fn join_all(mut self) -> Vec<thread::Result<T>> {
+        self.handles.drain(..).map(JoinHandle::join).collect()
+    }
+}
+
+/// Joins every thread, then re-raises the first panic among them, unless
+/// the set is being dropped by a panic already.
+impl<T> Drop for Workers<T> {
+    fn drop(&mut self) {
+        let mut first_panic = None;
+        for handle in self.handles.drain(..) {
+            if let Err(payload) = handle.join() {
+                first_panic.get_or_insert(payload);
+            }
+        }
+        if let Some(payload) = first_panic {
+            if !thread::panicking() {
+                panic::resume_unwind(payload);
+            }
+        }
+    }
+}
+
+/// A shared value that readers snapshot and writers replace.
+#[derive(Debug)]
+pub struct Config<T> {
+    current: Arc<RwLock<Arc<T>>>,
+}
+
+impl<T> Clone for Config<T> {
+    fn clone(&self) -> Self {
+        Config {
+            current: Arc::clone(&self.current),
+        }
+    }
+}
+
+impl<T> Config<T> {
+    pub fn new(value: T) -> Config<T> {
+        Config {
+            current: Arc::new(RwLock::new(Arc::new(value))),
+        }
+    }
+
+    /// The current value. Later updates don't change a snapshot.
+    pub fn snapshot(&self) -> Arc<T> {
+        let current = self.current.read().unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(&current)
+    }
+
+    /// Replaces the value, returning the old one.
+    pub fn replace(&self, value: T) -> Arc<T> {
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        std::mem::replace(&mut current, Arc::new(value))
+    }
+
+    /// Replaces the value with `f` of the current one. The write lock is
+    /// held throughout, so concurrent updates are never lost.
+    pub fn update(&self, f: impl FnOnce(&T) -> T) -> Arc<T> {
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        let new = Arc::new(f(&current));
+        *current = Arc::clone(&new);
+        new
+    }
+}
+
+#[derive(Debug)]
+struct State<T> {
+    version: u64,
+    value: Arc<T>,
+}
+
+#[derive(Debug)]
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    changed: Condvar,
+}
+
+/// A shared value with a version number that goes up by one with every
+/// change. The pair is read and written together, so a version always
+/// identifies a single value.
+#[derive(Debug)]
+pub struct Versioned<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for Versioned<T> {
+    fn clone(&self) -> Self {
+        Versioned {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Versioned<T> {
+    /// A cell holding `value` at version 0.
+    pub fn new(value: T) -> Versioned<T> {
+        Versioned {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    version: 0,
+                    value: Arc::new(value),
+                }),
+                changed: Condvar::new(),
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.shared
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The current version and value.
+    pub fn get(&self) -> (u64, Arc<T>) {
+        let state = self.lock();
+        (state.version, Arc::clone(&state.value))
+    }
+
+    pub fn version(&self) -> u64 {
+        self.lock().version
+    }
+
+    /// Stores `value`, returning its version.
+    pub fn set(&self, value: T) -> u64 {
+        self.update(|_| value)
+    }
+
+    /// Stores `f` of the current value, returning its version.
+    pub fn update(&self, f: impl FnOnce(&T) -> T) -> u64 {
+        let mut state = self.lock();
+        state.value = Arc::new(f(&state.value));
+        state.version += 1;
+        self.shared.changed.notify_all();
+        state.version
+    }
+
+    /// Blocks until the version is newer than `seen`.
+    pub fn wait_newer(&self, seen: u64) -> (u64, Arc<T>) {
+        self.wait_until(|version, _| version > seen)
+    }
+
+    /// Like `wait_newer`, giving up after `timeout`.
+    pub fn wait_newer_timeout(&self, seen: u64, timeout: Duration) -> Option<(u64, Arc<T>)> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.lock();
+        while state.version <= seen {
+            let left = deadline.checked_duration_since(Instant::now())?;
+            state = self
+                .shared
+                .changed
+                .wait_timeout(state, left)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+        Some((state.version, Arc::clone(&state.value)))
+    }
+
+    /// Blocks until `condition` holds for the version and value, checking
+    /// the current ones first.
+    pub fn wait_until(&self, mut condition: impl FnMut(u64, &T) -> bool) -> (u64, Arc<T>) {
+        let state = self
+            .shared
+            .changed
+            .wait_while(self.lock(), |state| !condition(state.version, &state.value))
+            .unwrap_or_else(PoisonError::into_inner);
+        (state.version, Arc::clone(&state.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+
+    #[test]
+    fn workers_are_joined_in_order() {
+        let mut workers = Workers::new();
+        for i in 0..8 {
+            workers.spawn(move || i * i);
+        }
+        workers
+            .spawn_named("failing", || panic!("worker failed"))
+            .unwrap();
+        let results = workers.join_all();
+        assert_eq!(results.len(), 9);
+        for (i, result) in results.iter().take(8).enumerate() {
+            assert_eq!(*result.as_ref().unwrap(), i * i);
+        }
+        let payload = results[8].as_ref().unwrap_err();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"worker failed"));
+    }
+
+    #[test]
+    fn dropping_workers_joins_them() {
+        let count = Arc::new(Mutex::new(0));
+        {
+            let mut workers = Workers::new();
+            for _ in 0..4 {
+                let count = Arc::clone(&count);
+                workers.spawn(move || *count.lock().unwrap() += 1);
+            }
+        }
+        assert_eq!(*count.lock().unwrap(), 4);
+    }
+
+    #[test]
+    fn dropping_workers_reraises_panics() {
+        let count = Arc::new(Mutex::new(0));
+        let dropped = panic::catch_unwind(|| {
+            let mut workers = Workers::new();
+            workers.spawn(|| panic!("worker failed"));
+            for _ in 0..4 {
+                let count = Arc::clone(&count);
+                workers.spawn(move || *count.lock().unwrap() += 1);
+            }
+        });
+        let payload = dropped.unwrap_err();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"worker failed"));
+        assert_eq!(*count.lock().unwrap(), 4);
+    }
+
+    #[test]
+    fn snapshots_are_consistent() {
+        let config = Config::new(vec![1, 2, 3]);
+        let before = config.snapshot();
+        let barrier = Arc::new(Barrier::new(9));
+        let mut workers = Workers::new();
+        for _ in 0..8 {
+            let (config, barrier) = (config.clone(), Arc::clone(&barrier));
+            workers.spawn(move || {
+                barrier.wait();
+                for _ in 0..100 {
+                    config.update(|v| v.iter().map(|x| x + 1).collect());
+                    let snapshot = config.snapshot();
+                    // every element is bumped together
+                    assert!(snapshot.windows(2).all(|w| w[1] == w[0] + 1));
+                }
+            });
+        }
+        barrier.wait();
+        for result in workers.join_all() {
+            result.unwrap();
+        }
+        assert_eq!(*before, [1, 2, 3]);
+        assert_eq!(*config.snapshot(), [801, 802, 803]);
+        assert_eq!(*config.replace(vec![]), [801, 802, 803]);
+    }
+
+    #[test]
+    fn waits_for_changes() {
+        let cell = Versioned::new(String::from("the same apple"));
+        let waiter = {
+            let cell = cell.clone();
+            thread::spawn(move || cell.wait_until(|_, value| value == "changed apple"))
+        };
+        let seen = cell.version();
+        let watcher = {
+            let cell = cell.clone();
+            thread::spawn(move || cell.wait_newer(seen))
+        };
+        assert_eq!(cell.set(String::from("still the same apple")), 1);
+        assert_eq!(watcher.join().unwrap().0, 1);
+        assert_eq!(cell.update(|_| String::from("changed apple")), 2);
+        let (version, value) = waiter.join().unwrap();
+        assert_eq!((version, value.as_str()), (2, "changed apple"));
+        assert!(cell.wait_newer_timeout(2, Duration::ZERO).is_none());
+        assert_eq!(cell.wait_newer_timeout(1, Duration::ZERO).unwrap().0, 2);
+    }
+
+    #[test]
+    fn versions_never_repeat() {
+        let cell = Versioned::new(0u64);
+        let mut workers = Workers::new();
+        for _ in 0..4 {
+            let cell = cell.clone();
+            workers.spawn(move || (0..250).map(|_| cell.update(|n| n + 1)).collect::<Vec<_>>());
+        }
+        let mut versions: Vec<u64> = workers
+            .join_all()
+            .into_iter()
+            .flat_map(Result::unwrap)
+            .collect();
+        versions.sort_unstable();
+        assert_eq!(versions, (1..=1000).collect::<Vec<_>>());
+        let (version, value) = cell.get();
+        assert_eq!(version, *value);
+    }
+}
@@ -0,0 +1,9 @@
+// Std library types library
+//
+// Reusable versions of the examples in `main.rs`.
+
+pub mod codec;
+pub mod concurrency;
+pub mod sets;
+pub mod text;
+pub mod tree;
@@ -195,6 +195,18 @@ fn main() {
 
     println!("Alice says: {}", alice);
     println!("Bob says: {}", bob);
+
+    // `std_library_types::text` does the same on real text: words are split
+    // by the Unicode rules rather than on whitespace alone
+    use std_library_types::text::{self, Case, Histogram};
+
+    println!("pangram? {}", text::is_pangram(pangram));
+    println!("words: {:?}", text::words("Don't panic, Zoë!").collect::<Vec<_>>());
+    println!("{}", Histogram::new(pangram));
+    println!("{}", text::to_case(pangram, Case::Pascal));
+    for line in text::wrap(pangram, 16) {
+        println!("|{:<16}|", line);
+    }
 }
 */
 
@@ -226,6 +238,8 @@ fn main() {
     // --------------
     let raw_str = r"Escapes don't work here: \x3F \u{211D}";
     println!("{}", raw_str);
+    // `text::unescape` resolves them the way the compiler would
+    println!("{:?}", std_library_types::text::unescape(raw_str));
 
     // If you need quotes in a raw string, add a pair of #s
     let quotes = r#"And then I said: "There is no escape!""#;
@@ -269,6 +283,14 @@ fn main() {
         Ok(my_str) => println!("Conversion successful: '{}'", my_str),
         Err(e) => println!("Conversion failed: {:?}", e),
     };
+
+    // `std_library_types::codec` shows bytes in more readable forms
+    use std_library_types::codec;
+
+    println!("hex: {}", codec::to_hex(shift_jis));
+    println!("escaped: b\"{}\"", codec::escape_bytes(shift_jis));
+    println!("base64: {}", codec::to_base64(bytestring));
+    print!("{}", codec::hexdump(bytestring));
 }
 */
 
@@ -666,6 +688,22 @@ fn main() {
         "sysmetic diff: {:>?}",
         a.symmetric_difference(&b).collect::<Vec<&i32>>()
     );
+
+    // `std_library_types::sets` works on any number of sets, and evaluates
+    // queries over named ones
+    use std_library_types::sets::{self, Sets};
+
+    let c: HashSet<i32> = vec![3, 5, 7].into_iter().collect();
+    println!("in at least two: {:?}", sets::at_least([&a, &b, &c], 2));
+
+    let mut groups = Sets::new();
+    groups.insert("admins", ["root", "alice"]).unwrap();
+    groups.insert("staff", ["alice", "bob", "carol"]).unwrap();
+    groups.insert("suspended", ["carol"]).unwrap();
+    println!(
+        "(admins | staff) & !suspended = {:?}",
+        groups.query("(admins | staff) & !suspended")
+    );
 }
 */
 
@@ -710,6 +748,23 @@ fn main() {
     }
 
     // println!("fc_examples: {}", rc_examples);
+
+    // `std_library_types::tree` builds a tree out of `Rc`s: parents own their
+    // children, and children point back with a `Weak`, which doesn't count as
+    // an owner
+    use std_library_types::tree::{LeakCheck, NodeRef};
+
+    let root = NodeRef::new("root");
+    let child = root.push("child");
+    child.push("grandchild");
+    println!("child: strong {}, weak {}", child.strong_count(), child.weak_count());
+
+    let check = LeakCheck::new(&root);
+    drop(root);
+    // `child` still owns its subtree
+    print!("{}", check.report());
+    drop(child);
+    print!("{}", check.report());
 }
 */
 
@@ -722,12 +777,17 @@ fn main() {
 // in the memory heap while increasing the reference counter. As it shares ownership between threads,
 // when the last reference pointer to a value is out of scope, the variable is dropped.
 
+//
+// The threads are joined rather than given time to finish with `thread::sleep`:
+// a sleep only makes it likely that they are done. `Workers` from
+// `std_library_types::concurrency` keeps the handles and joins them all.
+
 use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::Duration;
+use std_library_types::concurrency::{Versioned, Workers};
 
 fn main() {
     let apple = Arc::new(Mutex::new("the same apple"));
+    let mut workers = Workers::new();
 
     for i in 0..10 {
         let apple = Arc::clone(&apple);
@@ -737,14 +797,24 @@ fn main() {
             *apple_lock = &"changed apple";
         }
 
-        thread::spawn(move || {
+        workers.spawn(move || {
             println!("{:?}", apple);
         });
-
-        thread::sleep(Duration::from_millis(100));
     }
 
-    thread::sleep(Duration::from_secs(1));
+    for result in workers.join_all() {
+        result.expect("a worker panicked");
+    }
 
     println!("{:?}", apple);
+
+    // To wait for a change rather than poll for it, block on a `Versioned`
+    let basket = Versioned::new("the same apple");
+    let mut workers = Workers::new();
+    let watcher = basket.clone();
+    workers.spawn(move || {
+        let (version, apple) = watcher.wait_until(|_, apple| *apple == "changed apple");
+        println!("version {}: {:?}", version, apple);
+    });
+    basket.set("changed apple");
 }
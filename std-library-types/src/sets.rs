@@ -0,0 +1,645 @@
+// Sets
+//
+// Set algebra beyond the four `HashSet` operations of section 10: a counted
+// `Multiset`, operations over any number of sets at once, and `Sets`, a
+// collection of named sets that answers queries such as `(A | B) & !C`.
+//
+// Query syntax, from loosest to tightest binding:
+//
+//   - `a | b` union, `a ^ b` symmetric difference, `a - b` difference
+//   - `a & b` intersection
+//   - `!a` complement: everything in any named set, but not in `a`
+//
+// Operators of the same precedence associate to the left, and parentheses
+// group. Set names are made of letters, digits and `_`.
+
+use std::collections::hash_map;
+use std::collections::{HashMap, HashSet};
+use std::error;
+use std::fmt;
+use std::fs::{self, File};
+use std::hash::Hash;
+use std::io::{self, BufRead, BufReader};
+use std::iter::FromIterator;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// A set that counts how many times each element was inserted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Multiset<T: Eq + Hash> {
+    counts: HashMap<T, usize>,
+    len: usize,
+}
+
+impl<T: Eq + Hash> Default for Multiset<T> {
+    fn default() -> Self {
+        Multiset {
+            counts: HashMap::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T: Eq + Hash> Multiset<T> {
+    pub fn new() -> Self {
+        Multiset::default()
+    }
+
+    pub fn insert(&mut self, value: T) {
+        self.insert_n(value, 1);
+    }
+
+    pub fn insert_n(&mut self, value: T, n: usize) {
+        if n > 0 {
+            *self.counts.entry(value).or_insert(0) += n;
+            self.len += n;
+        }
+    }
+
+    /// Removes one occurrence of `value`, returning whether there was one.
+    pub fn remove(&mut self, value: &T) -> bool {
+        match self.counts.get_mut(value) {
+            Some(1) => {
+                self.counts.remove(value);
+            }
+            Some(n) => *n -= 1,
+            None => return false,
+        }
+        self.len -= 1;
+        true
+    }
+
+    pub fn count(&self, value: &T) -> usize {
+        self.counts.get(value).copied().unwrap_or(0)
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.counts.contains_key(value)
+    }
+
+    /// The number of elements, counting repeats.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The number of different elements.
+    pub fn distinct_len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Each distinct element with its count, in arbitrary order.
+    pub fn iter(&self) -> hash_map::Iter<'_, T, usize> {
+        self.counts.iter()
+    }
+
+    /// Whether every element occurs in `other` at least as often.
+    pub fn is_subset(&self, other: &Multiset<T>) -> bool {
+        self.counts.iter().all(|(v, &n)| n <= other.count(v))
+    }
+}
+
+impl<T: Eq + Hash + Clone> Multiset<T> {
+    fn combine(&self, other: &Multiset<T>, f: impl Fn(usize, usize) -> usize) -> Multiset<T> {
+        let mut result = Multiset::new();
+        for v in self.counts.keys().chain(other.counts.keys()) {
+            if !result.contains(v) {
+                result.insert_n(v.clone(), f(self.count(v), other.count(v)));
+            }
+        }
+        result
+    }
+
+    /// The larger count of each element.
+    pub fn union(&self, other: &Multiset<T>) -> Multiset<T> {
+        self.combine(other, usize::max)
+    }
+
+    /// The smaller count of each element.
+    pub fn intersection(&self, other: &Multiset<T>) -> Multiset<T> {
+        self.combine(other, usize::min)
+    }
+
+    /// The counts of `self` minus those of `other`, stopping at zero.
+    pub fn difference(&self, other: &Multiset<T>) -> Multiset<T> {
+        self.combine(other, usize::saturating_sub)
+    }
+
+    /// The counts of both added together.
+    pub fn sum(&self, other: &Multiset<T>) -> Multiset<T> {
+        self.combine(other, |a, b| a + b)
+    }
+}
+
+impl<T: Eq + Hash> FromIterator<T> for Multiset<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Multiset::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: Eq + Hash> Extend<T> for Multiset<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+/// For each element, the number of `sets` that contain it.
+pub fn membership<'a, T, I>(sets: I) -> Multiset<T>
+where
+    T: Eq + Hash + Clone + 'a,
+    I: IntoIterator<Item = &'a HashSet<T>>,
+{
+    sets.into_iter().flatten().cloned().collect()
+}
+
+/// The elements of any of `sets`.
+pub fn union_all<'a, T, I>(sets: I) -> HashSet<T>
+where
+    T: Eq + Hash + Clone + 'a,
+    I: IntoIterator<Item = &'a HashSet<T>>,
+{
+    sets.into_iter().flatten().cloned().collect()
+}
+
+/// The elements of every one of `sets`. Empty if there are no sets.
+pub fn intersection_all<'a, T, I>(sets: I) -> HashSet<T>
+where
+    T: Eq + Hash + Clone + 'a,
+    I: IntoIterator<Item = &'a HashSet<T>>,
+{
+    let mut sets = sets.into_iter();
+    let first = match sets.next() {
+        Some(first) => first.clone(),
+        None => return HashSet::new(),
+    };
+    sets.fold(first, |acc, set| acc.intersection(set).cloned().collect())
+}
+
+/// The elements of at least `n` of `sets`.
+pub fn at_least<'a, T, I>(sets: I, n: usize) -> HashSet<T>
+where
+    T: Eq + Hash + Clone + 'a,
+    I: IntoIterator<Item = &'a HashSet<T>>,
+{
+    membership(sets)
+        .iter()
+        .filter(|&(_, &count)| count >= n)
+        .map(|(v, _)| v.clone())
+        .collect()
+}
+
+/// The elements of exactly one of `sets`. For two sets this is the
+/// symmetric difference.
+pub fn exactly_one<'a, T, I>(sets: I) -> HashSet<T>
+where
+    T: Eq + Hash + Clone + 'a,
+    I: IntoIterator<Item = &'a HashSet<T>>,
+{
+    membership(sets)
+        .iter()
+        .filter(|&(_, &count)| count == 1)
+        .map(|(v, _)| v.clone())
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum SetError {
+    UnknownSet(String),
+    /// A set name that queries couldn't refer to.
+    InvalidName(String),
+    /// Two files in a directory define the same set.
+    Duplicate(String),
+    /// A query that could not be parsed; `position` is a byte offset.
+    Syntax {
+        position: usize,
+        reason: &'static str,
+    },
+    Io(io::Error),
+}
+
+impl fmt::Display for SetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SetError::UnknownSet(name) => write!(f, "no set named {:?}", name),
+            SetError::InvalidName(name) => write!(f, "{:?} is not a valid set name", name),
+            SetError::Duplicate(name) => write!(f, "more than one file defines {:?}", name),
+            SetError::Syntax { position, reason } => write!(f, "at {}: {}", position, reason),
+            SetError::Io(..) => write!(f, "could not read the set file"),
+        }
+    }
+}
+
+impl error::Error for SetError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SetError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SetError {
+    fn from(e: io::Error) -> Self {
+        SetError::Io(e)
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(is_name_char)
+}
+
+// The file stem, if it is a valid set name.
+fn set_name(path: &Path) -> Option<&str> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .filter(|name| is_valid_name(name))
+}
+
+/// A parsed query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    Set(String),
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Xor(Box<Query>, Box<Query>),
+    Minus(Box<Query>, Box<Query>),
+}
+
+/// Fully parenthesised, so that it parses back to the same query.
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Query::Set(name) => write!(f, "{}", name),
+            Query::Not(q) => write!(f, "!{}", q),
+            Query::And(a, b) => write!(f, "({} & {})", a, b),
+            Query::Or(a, b) => write!(f, "({} | {})", a, b),
+            Query::Xor(a, b) => write!(f, "({} ^ {})", a, b),
+            Query::Minus(a, b) => write!(f, "({} - {})", a, b),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = SetError;
+
+    fn from_str(s: &str) -> Result<Query, SetError> {
+        let mut parser = Parser {
+            text: s,
+            position: 0,
+        };
+        let query = parser.expression()?;
+        match parser.peek() {
+            None => Ok(query),
+            Some(_) => Err(parser.error("expected an operator")),
+        }
+    }
+}
+
+// A recursive descent parser, one function per precedence level.
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, reason: &'static str) -> SetError {
+        SetError::Syntax {
+            position: self.position,
+            reason,
+        }
+    }
+
+    /// The next non-space character, without consuming it.
+    fn peek(&mut self) -> Option<char> {
+        let rest = &self.text[self.position..];
+        let trimmed = rest.trim_start();
+        self.position += rest.len() - trimmed.len();
+        trimmed.chars().next()
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            self.position += c.len_utf8();
+        }
+    }
+
+    fn expression(&mut self) -> Result<Query, SetError> {
+        let mut query = self.term()?;
+        while let Some(op @ ('|' | '^' | '-')) = self.peek() {
+            self.bump();
+            let rhs = Box::new(self.term()?);
+            let lhs = Box::new(query);
+            query = match op {
+                '|' => Query::Or(lhs, rhs),
+                '^' => Query::Xor(lhs, rhs),
+                _ => Query::Minus(lhs, rhs),
+            };
+        }
+        Ok(query)
+    }
+
+    fn term(&mut self) -> Result<Query, SetError> {
+        let mut query = self.factor()?;
+        while self.peek() == Some('&') {
+            self.bump();
+            query = Query::And(Box::new(query), Box::new(self.factor()?));
+        }
+        Ok(query)
+    }
+
+    fn factor(&mut self) -> Result<Query, SetError> {
+        match self.peek() {
+            Some('!') => {
+                self.bump();
+                Ok(Query::Not(Box::new(self.factor()?)))
+            }
+            Some('(') => {
+                self.bump();
+                let query = self.expression()?;
+                if self.peek() != Some(')') {
+                    return Err(self.error("expected `)`"));
+                }
+                self.bump();
+                Ok(query)
+            }
+            Some(c) if is_name_char(c) => {
+                let rest = &self.text[self.position..];
+                let len = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+                self.position += len;
+                Ok(Query::Set(rest[..len].to_string()))
+            }
+            Some(_) => Err(self.error("expected a set name, `!` or `(`")),
+            None => Err(self.error("unexpected end of query")),
+        }
+    }
+}
+
+/// Named sets of strings.
+#[derive(Debug, Clone, Default)]
+pub struct Sets {
+    sets: HashMap<String, HashSet<String>>,
+}
+
+impl Sets {
+    pub fn new() -> Sets {
+        Sets::default()
+    }
+
+    /// Adds or replaces the set `name`.
+    pub fn insert<I, S>(&mut self, name: &str, members: I) -> Result<(), SetError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        if !is_valid_name(name) {
+            return Err(SetError::InvalidName(name.to_string()));
+        }
+        let members = members.into_iter().map(Into::into).collect();
+        self.sets.insert(name.to_string(), members);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&HashSet<String>> {
+        self.sets.get(name)
+    }
+
+    /// The set names, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.sets.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Reads a set with one member per line. Surrounding whitespace is
+    /// trimmed, and blank lines and lines starting with `#` are skipped.
+    pub fn read_from<R: BufRead>(&mut self, name: &str, reader: R) -> Result<(), SetError> {
+        let mut members = HashSet::new();
+        for line in reader.lines() {
+            let line = line?;
+            let member = line.trim();
+            if !member.is_empty() && !member.starts_with('#') {
+                members.insert(member.to_string());
+            }
+        }
+        self.insert(name, members)
+    }
+
+    /// Loads a set file, named after the file without its extension.
+    pub fn load(&mut self, path: &Path) -> Result<(), SetError> {
+        let name =
+            set_name(path).ok_or_else(|| SetError::InvalidName(path.display().to_string()))?;
+        self.read_from(name, BufReader::new(File::open(path)?))
+    }
+
+    /// Loads every file in `dir`, see `load`. Files whose names aren't valid
+    /// set names, such as `.DS_Store` or `x.tar.gz`, are skipped and returned
+    /// sorted; two files that define the same set are an error.
+    pub fn load_dir(dir: &Path) -> Result<(Sets, Vec<PathBuf>), SetError> {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                paths.push(entry.path());
+            }
+        }
+        paths.sort_unstable();
+
+        let mut sets = Sets::new();
+        let mut skipped = Vec::new();
+        for path in paths {
+            match set_name(&path) {
+                Some(name) if sets.sets.contains_key(name) => {
+                    return Err(SetError::Duplicate(name.to_string()));
+                }
+                Some(_) => sets.load(&path)?,
+                None => skipped.push(path),
+            }
+        }
+        Ok((sets, skipped))
+    }
+
+    /// Evaluates `query`, returning its members sorted.
+    ///
+    ///     use std_library_types::sets::Sets;
+    ///
+    ///     let mut sets = Sets::new();
+    ///     sets.insert("admins", ["root", "alice"]).unwrap();
+    ///     sets.insert("staff", ["alice", "bob", "carol"]).unwrap();
+    ///     sets.insert("suspended", ["carol"]).unwrap();
+    ///     assert_eq!(sets.query("(admins | staff) & !suspended").unwrap(), ["alice", "bob", "root"]);
+    pub fn query(&self, query: &str) -> Result<Vec<String>, SetError> {
+        self.evaluate(&query.parse()?)
+    }
+
+    pub fn evaluate(&self, query: &Query) -> Result<Vec<String>, SetError> {
+        let mut members: Vec<String> = self.eval(query)?.into_iter().map(str::to_string).collect();
+        members.sort_unstable();
+        Ok(members)
+    }
+
+    fn eval(&self, query: &Query) -> Result<HashSet<&str>, SetError> {
+        let binary = |a, b| -> Result<(HashSet<&str>, HashSet<&str>), SetError> {
+            Ok((self.eval(a)?, self.eval(b)?))
+        };
+        Ok(match query {
+            Query::Set(name) => self
+                .sets
+                .get(name)
+                .ok_or_else(|| SetError::UnknownSet(name.clone()))?
+                .iter()
+                .map(String::as_str)
+                .collect(),
+            Query::Not(q) => {
+                let excluded = self.eval(q)?;
+                self.universe().filter(|m| !excluded.contains(m)).collect()
+            }
+            Query::And(a, b) => {
+                let (a, b) = binary(a, b)?;
+                a.intersection(&b).copied().collect()
+            }
+            Query::Or(a, b) => {
+                let (a, b) = binary(a, b)?;
+                a.union(&b).copied().collect()
+            }
+            Query::Xor(a, b) => {
+                let (a, b) = binary(a, b)?;
+                a.symmetric_difference(&b).copied().collect()
+            }
+            Query::Minus(a, b) => {
+                let (a, b) = binary(a, b)?;
+                a.difference(&b).copied().collect()
+            }
+        })
+    }
+
+    /// Every member of any set.
+    fn universe(&self) -> impl Iterator<Item = &str> {
+        let all: HashSet<&str> = self.sets.values().flatten().map(String::as_str).collect();
+        all.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    fn set(items: &[i32]) -> HashSet<i32> {
+        items.iter().copied().collect()
+    }
+
+    #[test]
+    fn multiset_counts() {
+        let mut a: Multiset<char> = "hello".chars().collect();
+        assert_eq!(a.count(&'l'), 2);
+        assert_eq!((a.len(), a.distinct_len()), (5, 4));
+        assert!(a.remove(&'l'));
+        assert!(a.remove(&'h'));
+        assert!(!a.remove(&'h'));
+        assert!(!a.contains(&'h'));
+        assert_eq!(a.len(), 3);
+
+        let b: Multiset<char> = "lollipop".chars().collect();
+        let a: Multiset<char> = "hello".chars().collect();
+        assert_eq!(a.union(&b).count(&'l'), 3);
+        assert_eq!(a.intersection(&b).count(&'o'), 1);
+        assert_eq!(a.intersection(&b).len(), 3);
+        assert_eq!(a.difference(&b), "he".chars().collect());
+        assert_eq!(a.sum(&b).len(), 13);
+        assert!("lol".chars().collect::<Multiset<_>>().is_subset(&b));
+        assert!(!a.is_subset(&b));
+    }
+
+    #[test]
+    fn operates_on_many_sets() {
+        let sets = [set(&[1, 2, 3]), set(&[2, 3, 4]), set(&[3, 4, 5])];
+        assert_eq!(union_all(&sets), set(&[1, 2, 3, 4, 5]));
+        assert_eq!(intersection_all(&sets), set(&[3]));
+        assert_eq!(at_least(&sets, 2), set(&[2, 3, 4]));
+        assert_eq!(exactly_one(&sets), set(&[1, 5]));
+        assert_eq!(
+            exactly_one(&sets[..2]),
+            sets[0].symmetric_difference(&sets[1]).copied().collect()
+        );
+        assert_eq!(membership(&sets).count(&4), 2);
+        assert!(intersection_all::<i32, _>(&[]).is_empty());
+    }
+
+    #[test]
+    fn parses_queries() {
+        let q: Query = "(A | B) & !C".parse().unwrap();
+        assert_eq!(q.to_string(), "((A | B) & !C)");
+        assert_eq!(
+            "A | B & C".parse::<Query>().unwrap().to_string(),
+            "(A | (B & C))"
+        );
+        assert_eq!(
+            "A - B - C".parse::<Query>().unwrap().to_string(),
+            "((A - B) - C)"
+        );
+        assert_eq!("!!A ^ B".parse::<Query>().unwrap().to_string(), "(!!A ^ B)");
+        assert_eq!(q.to_string().parse::<Query>().unwrap(), q);
+        let position = |query: &str| match query.parse::<Query>() {
+            Err(SetError::Syntax { position, .. }) => position,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(position("(A | B"), 6);
+        assert_eq!(position("A B"), 2);
+        assert_eq!(position("A | "), 4);
+        assert_eq!(position("A & * B"), 4);
+    }
+
+    #[test]
+    fn evaluates_queries() {
+        let mut sets = Sets::new();
+        sets.insert("A", ["1", "2", "3"]).unwrap();
+        sets.insert("B", ["3", "4"]).unwrap();
+        sets.insert("C", ["2", "4", "5"]).unwrap();
+        assert_eq!(sets.query("(A | B) & !C").unwrap(), ["1", "3"]);
+        assert_eq!(sets.query("!A").unwrap(), ["4", "5"]);
+        assert_eq!(sets.query("A ^ C").unwrap(), ["1", "3", "4", "5"]);
+        assert_eq!(sets.query("A - B - C").unwrap(), ["1"]);
+        assert!(sets.query("A & B & C").unwrap().is_empty());
+        assert!(matches!(sets.query("A | D"), Err(SetError::UnknownSet(name)) if name == "D"));
+        assert!(matches!(
+            sets.insert("a b", ["x"]),
+            Err(SetError::InvalidName(_))
+        ));
+    }
+
+    #[test]
+    fn loads_set_files() {
+        let dir = env::temp_dir().join(format!("sets-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("admins.txt"), "# admins\nroot\n  alice  \n\n").unwrap();
+        fs::write(dir.join("staff.txt"), "alice\nbob\n").unwrap();
+        fs::write(dir.join(".DS_Store"), [0, 1, 2]).unwrap();
+        fs::write(dir.join("my-group.txt"), "eve\n").unwrap();
+        fs::write(dir.join("x.tar.gz"), "").unwrap();
+        let loaded = Sets::load_dir(&dir);
+        fs::write(dir.join("admins.csv"), "mallory\n").unwrap();
+        let duplicate = Sets::load_dir(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let (sets, skipped) = loaded.unwrap();
+        let skipped: Vec<_> = skipped.iter().map(|p| p.file_name().unwrap()).collect();
+        assert_eq!(skipped, [".DS_Store", "my-group.txt", "x.tar.gz"]);
+        assert!(matches!(duplicate, Err(SetError::Duplicate(name)) if name == "admins"));
+        assert_eq!(sets.names(), ["admins", "staff"]);
+        assert_eq!(sets.get("admins").unwrap().len(), 2);
+        assert_eq!(sets.query("staff - admins").unwrap(), ["bob"]);
+    }
+}
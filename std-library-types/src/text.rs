@@ -0,0 +1,542 @@
+// Text
+//
+// String utilities for real text, built on the string handling of section 03.
+// Boundaries follow Unicode: `words` uses the UAX #29 word rules, `wrap` breaks
+// between grapheme clusters and measures their display width, so `"日本"` takes
+// four columns and `"e\u{301}"` one.
+
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// The words of `text`, without the spaces and punctuation between them.
+///
+///     let words: Vec<_> = std_library_types::text::words("Don't panic, Zoë!").collect();
+///     assert_eq!(words, ["Don't", "panic", "Zoë"]);
+pub fn words(text: &str) -> impl DoubleEndedIterator<Item = &str> {
+    text.unicode_words()
+}
+
+/// Splits an identifier-like string into words: at anything that isn't a
+/// letter or digit, and where the case changes, so `"parseHTTPRequest"` gives
+/// `parse`, `HTTP` and `Request`.
+pub fn identifier_words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    for part in text.split(|c: char| !c.is_alphanumeric()) {
+        let chars: Vec<(usize, char)> = part.char_indices().collect();
+        let mut start = 0;
+        for i in 1..chars.len() {
+            let (at, c) = chars[i];
+            let prev = chars[i - 1].1;
+            let next_is_lower = chars.get(i + 1).is_some_and(|&(_, n)| n.is_lowercase());
+            let hump = c.is_uppercase()
+                && (prev.is_lowercase()
+                    || prev.is_numeric()
+                    || prev.is_uppercase() && next_is_lower);
+            if hump {
+                words.push(&part[start..at]);
+                start = at;
+            }
+        }
+        if start < part.len() {
+            words.push(&part[start..]);
+        }
+    }
+    words
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Case {
+    /// `snake_case`
+    Snake,
+    /// `SCREAMING_SNAKE_CASE`
+    ScreamingSnake,
+    /// `kebab-case`
+    Kebab,
+    /// `camelCase`
+    Camel,
+    /// `PascalCase`
+    Pascal,
+    /// `Title Case`
+    Title,
+}
+
+/// Rewrites `text` in `case`, splitting it with `identifier_words`.
+///
+///     use std_library_types::text::{to_case, Case};
+///
+///     assert_eq!(to_case("XMLHttpRequest", Case::Snake), "xml_http_request");
+///     assert_eq!(to_case("the quick-brown fox", Case::Camel), "theQuickBrownFox");
+pub fn to_case(text: &str, case: Case) -> String {
+    let words = identifier_words(text);
+    let separator = match case {
+        Case::Snake | Case::ScreamingSnake => "_",
+        Case::Kebab => "-",
+        Case::Camel | Case::Pascal => "",
+        Case::Title => " ",
+    };
+    let mut out = String::with_capacity(text.len());
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            out.push_str(separator);
+        }
+        match case {
+            Case::Snake | Case::Kebab => out.push_str(&word.to_lowercase()),
+            Case::ScreamingSnake => out.push_str(&word.to_uppercase()),
+            Case::Camel if i == 0 => out.push_str(&word.to_lowercase()),
+            Case::Camel | Case::Pascal | Case::Title => capitalize_into(word, &mut out),
+        }
+    }
+    out
+}
+
+fn capitalize_into(word: &str, out: &mut String) {
+    let mut chars = word.chars();
+    if let Some(first) = chars.next() {
+        out.extend(first.to_uppercase());
+        out.push_str(&chars.as_str().to_lowercase());
+    }
+}
+
+/// The letters of the English alphabet that `text` doesn't use, ignoring
+/// case.
+pub fn missing_letters(text: &str) -> Vec<char> {
+    let mut seen = [false; 26];
+    for c in text.chars().filter(char::is_ascii_alphabetic) {
+        seen[(c.to_ascii_lowercase() as u8 - b'a') as usize] = true;
+    }
+    ('a'..='z')
+        .filter(|c| !seen[(*c as u8 - b'a') as usize])
+        .collect()
+}
+
+/// Whether `text` uses every letter of the English alphabet.
+///
+///     use std_library_types::text::is_pangram;
+///
+///     assert!(is_pangram("The quick brown fox jumps over the lazy dog"));
+///     assert!(!is_pangram("the quick brown fox jumped over the lazy dog"));
+pub fn is_pangram(text: &str) -> bool {
+    missing_letters(text).is_empty()
+}
+
+/// Whether `a` and `b` use the same letters and digits the same number of
+/// times, ignoring case, spaces and punctuation. Accented letters are
+/// compared as whole graphemes, so `"é"` is not an anagram of `"e"`.
+///
+///     use std_library_types::text::is_anagram;
+///
+///     assert!(is_anagram("Dormitory", "dirty room!"));
+///     assert!(!is_anagram("listen", "silence"));
+pub fn is_anagram(a: &str, b: &str) -> bool {
+    fn letters(text: &str) -> Vec<String> {
+        let lower = text.to_lowercase();
+        let mut graphemes: Vec<String> = lower
+            .graphemes(true)
+            .filter(|g| g.chars().next().is_some_and(char::is_alphanumeric))
+            .map(str::to_string)
+            .collect();
+        graphemes.sort();
+        graphemes
+    }
+    letters(a) == letters(b)
+}
+
+/// How often each character appears in a text, whitespace excluded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Histogram {
+    counts: BTreeMap<char, usize>,
+}
+
+impl Histogram {
+    pub fn new(text: &str) -> Histogram {
+        let mut counts = BTreeMap::new();
+        for c in text.chars().filter(|c| !c.is_whitespace()) {
+            *counts.entry(c).or_insert(0) += 1;
+        }
+        Histogram { counts }
+    }
+
+    pub fn count(&self, c: char) -> usize {
+        self.counts.get(&c).copied().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// Characters from most to least frequent; ties in character order.
+    pub fn most_common(&self) -> Vec<(char, usize)> {
+        let mut counts: Vec<(char, usize)> = self.counts.iter().map(|(&c, &n)| (c, n)).collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        counts
+    }
+}
+
+/// One line per character, in character order, with a bar of `#`s. Bars are
+/// scaled so that the longest is 40 wide.
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const BAR: usize = 40;
+        let max = self.counts.values().copied().max().unwrap_or(0);
+        for (&c, &n) in &self.counts {
+            let bar = (n * BAR).div_ceil(max);
+            writeln!(
+                f,
+                "{} | {:<width$} {}",
+                c.escape_debug(),
+                "#".repeat(bar),
+                n,
+                width = BAR
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Wraps `text` into lines at most `width` columns wide, breaking at
+/// whitespace. Newlines in `text` are kept, and words wider than `width` are
+/// split between graphemes. A `width` of 0 is treated as 1.
+///
+///     use std_library_types::text::wrap;
+///
+///     assert_eq!(wrap("the quick brown fox", 10), ["the quick", "brown fox"]);
+///     assert_eq!(wrap("日本語のテキスト", 6), ["日本語", "のテキ", "スト"]);
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        let mut line_width = 0;
+        for word in paragraph.split_whitespace() {
+            let word_width = word.width();
+            if line_width > 0 && line_width + 1 + word_width <= width {
+                line.push(' ');
+                line.push_str(word);
+                line_width += 1 + word_width;
+                continue;
+            }
+            if line_width > 0 {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            if word_width <= width {
+                line.push_str(word);
+                line_width = word_width;
+                continue;
+            }
+            for grapheme in word.graphemes(true) {
+                let grapheme_width = grapheme.width();
+                if line_width > 0 && line_width + grapheme_width > width {
+                    lines.push(std::mem::take(&mut line));
+                    line_width = 0;
+                }
+                line.push_str(grapheme);
+                line_width += grapheme_width;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Escapes `text` the way `{:?}` does, without the surrounding quotes:
+/// quotes, backslashes and control characters are escaped, printable
+/// characters are kept.
+pub fn escape(text: &str) -> String {
+    text.escape_debug().to_string()
+}
+
+/// Like `escape`, but everything outside printable ASCII is escaped, with
+/// `\x` for ASCII and `\u{..}` otherwise.
+///
+///     use std_library_types::text::escape_ascii;
+///
+///     assert_eq!(escape_ascii("ℝ\t\"\x7f\""), r#"\u{211d}\t\"\x7f\""#);
+pub fn escape_ascii(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\0' => out.push_str("\\0"),
+            '"' | '\'' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            c if c.is_ascii() => out.push_str(&format!("\\x{:02x}", c as u32)),
+            c => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnescapeErrorKind {
+    UnknownEscape(char),
+    /// `\x` not followed by two hex digits up to `7f`.
+    BadHexEscape,
+    /// `\u` not followed by `{..}` with a valid code point.
+    BadUnicodeEscape,
+    TrailingBackslash,
+    /// Not a quoted or raw string literal.
+    BadLiteral,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnescapeError {
+    /// Byte offset of the offending escape.
+    pub position: usize,
+    pub kind: UnescapeErrorKind,
+}
+
+impl fmt::Display for UnescapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            UnescapeErrorKind::UnknownEscape(c) => {
+                write!(
+                    f,
+                    "unknown escape \\{} at {}",
+                    c.escape_debug(),
+                    self.position
+                )
+            }
+            UnescapeErrorKind::BadHexEscape => write!(f, "bad \\x escape at {}", self.position),
+            UnescapeErrorKind::BadUnicodeEscape => {
+                write!(f, "bad \\u escape at {}", self.position)
+            }
+            UnescapeErrorKind::TrailingBackslash => {
+                write!(f, "trailing backslash at {}", self.position)
+            }
+            UnescapeErrorKind::BadLiteral => write!(f, "not a string literal"),
+        }
+    }
+}
+
+impl error::Error for UnescapeError {}
+
+/// Resolves the escapes of a Rust string literal body: `\n`, `\r`, `\t`,
+/// `\0`, `\\`, quotes, `\x52`, `\u{211D}`, and a backslash before a newline,
+/// which skips the newline and the indentation after it.
+///
+///     use std_library_types::text::unescape;
+///
+///     assert_eq!(unescape(r"I'm writing \x52\x75\x73\x74!").unwrap(), "I'm writing Rust!");
+///     assert_eq!(unescape(r"\u{211D}").unwrap(), "ℝ");
+pub fn unescape(text: &str) -> Result<String, UnescapeError> {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let error = |kind| UnescapeError { position, kind };
+        let (_, escape) = chars
+            .next()
+            .ok_or(error(UnescapeErrorKind::TrailingBackslash))?;
+        match escape {
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            '0' => out.push('\0'),
+            '\\' | '\'' | '"' => out.push(escape),
+            '\n' => while chars.next_if(|&(_, c)| c.is_whitespace()).is_some() {},
+            'x' => {
+                let start = position + 2;
+                let digits = text
+                    .get(start..start + 2)
+                    .ok_or(error(UnescapeErrorKind::BadHexEscape))?;
+                let value = u8::from_str_radix(digits, 16)
+                    .ok()
+                    .filter(|&b| b <= 0x7f && digits.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .ok_or(error(UnescapeErrorKind::BadHexEscape))?;
+                out.push(char::from(value));
+                chars.next();
+                chars.next();
+            }
+            'u' => {
+                let start = position + 2;
+                let end = text[start..]
+                    .find('}')
+                    .map(|i| start + i)
+                    .filter(|_| text[start..].starts_with('{'))
+                    .ok_or(error(UnescapeErrorKind::BadUnicodeEscape))?;
+                let digits = text[start + 1..end].replace('_', "");
+                let c = Some(digits.as_str())
+                    .filter(|d| {
+                        (1..=6).contains(&d.len()) && d.bytes().all(|b| b.is_ascii_hexdigit())
+                    })
+                    .and_then(|d| u32::from_str_radix(d, 16).ok())
+                    .and_then(char::from_u32)
+                    .ok_or(error(UnescapeErrorKind::BadUnicodeEscape))?;
+                out.push(c);
+                while chars.next_if(|&(i, _)| i <= end).is_some() {}
+            }
+            other => return Err(error(UnescapeErrorKind::UnknownEscape(other))),
+        }
+    }
+    Ok(out)
+}
+
+/// `text` as a raw string literal, with as few `#`s as it needs.
+///
+///     use std_library_types::text::to_raw_literal;
+///
+///     assert_eq!(to_raw_literal(r"\x3F"), r##"r"\x3F""##);
+///     assert_eq!(to_raw_literal(r##"A string with "# in it"##), r###"r##"A string with "# in it"##"###);
+pub fn to_raw_literal(text: &str) -> String {
+    // the delimiter needs one more `#` than the longest `"#...` in the text
+    let hashes = text
+        .match_indices('"')
+        .map(|(i, _)| 1 + text[i + 1..].bytes().take_while(|&b| b == b'#').count())
+        .max()
+        .unwrap_or(0);
+    let hashes = "#".repeat(hashes);
+    format!("r{}\"{}\"{}", hashes, text, hashes)
+}
+
+/// The value of a string literal, either quoted with escapes or raw.
+///
+///     use std_library_types::text::parse_literal;
+///
+///     assert_eq!(parse_literal(r#""\u{211D}""#).unwrap(), "ℝ");
+///     assert_eq!(parse_literal(r##"r#"There is no "escape"!"#"##).unwrap(), r#"There is no "escape"!"#);
+pub fn parse_literal(literal: &str) -> Result<String, UnescapeError> {
+    let bad = UnescapeError {
+        position: 0,
+        kind: UnescapeErrorKind::BadLiteral,
+    };
+    if let Some(raw) = literal.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let delimiter = &raw[..hashes];
+        return raw[hashes..]
+            .strip_prefix('"')
+            .and_then(|body| body.strip_suffix(delimiter))
+            .and_then(|body| body.strip_suffix('"'))
+            .filter(|body| !body.contains(&format!("\"{}", delimiter)))
+            .map(str::to_string)
+            .ok_or(bad);
+    }
+    let body = literal
+        .strip_prefix('"')
+        .and_then(|body| body.strip_suffix('"'))
+        .ok_or(bad)?;
+    unescape(body).map_err(|e| UnescapeError {
+        position: e.position + 1,
+        ..e
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_identifiers() {
+        assert_eq!(
+            identifier_words("parseHTTPRequest"),
+            ["parse", "HTTP", "Request"]
+        );
+        assert_eq!(
+            identifier_words("snake_case-and kebab"),
+            ["snake", "case", "and", "kebab"]
+        );
+        assert_eq!(identifier_words("utf8Decoder2"), ["utf8", "Decoder2"]);
+        assert_eq!(identifier_words("ÉtéÀParis"), ["Été", "À", "Paris"]);
+        assert!(identifier_words("  --  ").is_empty());
+    }
+
+    #[test]
+    fn converts_case() {
+        let text = "the quick brown_fox";
+        assert_eq!(to_case(text, Case::Snake), "the_quick_brown_fox");
+        assert_eq!(to_case(text, Case::ScreamingSnake), "THE_QUICK_BROWN_FOX");
+        assert_eq!(to_case(text, Case::Kebab), "the-quick-brown-fox");
+        assert_eq!(to_case(text, Case::Camel), "theQuickBrownFox");
+        assert_eq!(to_case(text, Case::Pascal), "TheQuickBrownFox");
+        assert_eq!(to_case(text, Case::Title), "The Quick Brown Fox");
+        assert_eq!(to_case("straße", Case::ScreamingSnake), "STRASSE");
+    }
+
+    #[test]
+    fn counts_characters() {
+        let histogram = Histogram::new("hello world");
+        assert_eq!(histogram.count('l'), 3);
+        assert_eq!(histogram.count(' '), 0);
+        assert_eq!(histogram.total(), 10);
+        assert_eq!(histogram.most_common()[..2], [('l', 3), ('o', 2)]);
+        let rendered = Histogram::new("aab").to_string();
+        assert_eq!(
+            rendered.lines().next().unwrap(),
+            format!("a | {} 2", "#".repeat(40))
+        );
+        assert!(rendered
+            .lines()
+            .nth(1)
+            .unwrap()
+            .starts_with(&format!("b | {} ", "#".repeat(20))));
+    }
+
+    #[test]
+    fn wraps_by_display_width() {
+        assert!(wrap("", 10).is_empty());
+        assert_eq!(wrap("a\n\nb c", 1), ["a", "", "b", "c"]);
+        assert_eq!(
+            wrap("supercalifragilistic", 8),
+            ["supercal", "ifragili", "stic"]
+        );
+        // a combining accent takes no column of its own
+        assert_eq!(
+            wrap("cafe\u{301} cafe\u{301}", 9),
+            ["cafe\u{301} cafe\u{301}"]
+        );
+        for line in wrap("ようこそ、世界へ。 Welcome to the world.", 7) {
+            assert!(line.width() <= 7, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn escapes_round_trip() {
+        let samples = [
+            "I'm writing Rust!",
+            "tab\there\n",
+            "ℝ \"quoted\" \\",
+            "\u{0}\u{7f}\u{1F980}",
+        ];
+        for text in samples {
+            assert_eq!(unescape(&escape(text)).unwrap(), text);
+            assert_eq!(unescape(&escape_ascii(text)).unwrap(), text);
+            assert!(escape_ascii(text).is_ascii());
+            assert_eq!(parse_literal(&to_raw_literal(text)).unwrap(), text);
+            assert_eq!(parse_literal(&format!("{:?}", text)).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn unescapes_like_the_compiler() {
+        let long = "String literals\n    can span multiple lines.\\\n    <- escaped";
+        assert_eq!(
+            unescape(long).unwrap(),
+            "String literals\n    can span multiple lines.<- escaped"
+        );
+        assert_eq!(unescape(r"\u{1_F980}").unwrap(), "\u{1F980}");
+        let kind = |text| unescape(text).unwrap_err().kind;
+        assert_eq!(kind(r"\x80"), UnescapeErrorKind::BadHexEscape);
+        assert_eq!(kind(r"\x4"), UnescapeErrorKind::BadHexEscape);
+        assert_eq!(kind(r"\x+1"), UnescapeErrorKind::BadHexEscape);
+        assert_eq!(kind(r"\u{D800}"), UnescapeErrorKind::BadUnicodeEscape);
+        assert_eq!(kind(r"\u211D"), UnescapeErrorKind::BadUnicodeEscape);
+        assert_eq!(kind(r"\q"), UnescapeErrorKind::UnknownEscape('q'));
+        assert_eq!(kind("abc\\"), UnescapeErrorKind::TrailingBackslash);
+        assert_eq!(unescape(r"ok \q").unwrap_err().position, 3);
+        assert!(parse_literal(r##"r#"x"##).is_err());
+        assert!(parse_literal(r#"r"a"b""#).is_err());
+        assert!(parse_literal("plain").is_err());
+    }
+}
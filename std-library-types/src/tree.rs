@@ -0,0 +1,437 @@
+// Tree
+//
+// A tree of `Rc<RefCell<Node>>`s, as used for document models. Children are
+// owned by their parent through strong references, and each child points
+// back at its parent through a `Weak` one, so a tree with no handles left is
+// freed as a whole.
+//
+// That only holds if nothing else closes a loop of strong references: a node
+// whose value holds a `NodeRef` to one of its ancestors keeps itself alive.
+// `add_child` refuses to create a cycle through child links, and a
+// `LeakCheck` taken before dropping the root lists the nodes that survived,
+// with their reference counts.
+
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::VecDeque;
+use std::error;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+#[derive(Debug)]
+struct Node<T> {
+    value: T,
+    parent: Weak<RefCell<Node<T>>>,
+    children: Vec<NodeRef<T>>,
+}
+
+/// A shared handle to a node. Cloning it adds a strong reference.
+pub struct NodeRef<T>(Rc<RefCell<Node<T>>>);
+
+impl<T> Clone for NodeRef<T> {
+    fn clone(&self) -> Self {
+        NodeRef(Rc::clone(&self.0))
+    }
+}
+
+// Shows the value and the subtree, but not the parent, which would print the
+// whole tree again.
+impl<T: fmt::Debug> fmt::Debug for NodeRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let node = self.0.borrow();
+        f.debug_struct("NodeRef")
+            .field("value", &node.value)
+            .field("children", &node.children)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeError {
+    /// The node already has a parent; `detach` it first.
+    AlreadyAttached,
+    /// The node is the new parent or one of its ancestors.
+    Cycle,
+}
+
+impl fmt::Display for TreeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TreeError::AlreadyAttached => write!(f, "the node already has a parent"),
+            TreeError::Cycle => write!(f, "the node is an ancestor of its new parent"),
+        }
+    }
+}
+
+impl error::Error for TreeError {}
+
+impl<T> NodeRef<T> {
+    /// A new node without parent or children.
+    pub fn new(value: T) -> NodeRef<T> {
+        NodeRef(Rc::new(RefCell::new(Node {
+            value,
+            parent: Weak::new(),
+            children: Vec::new(),
+        })))
+    }
+
+    pub fn value(&self) -> Ref<'_, T> {
+        Ref::map(self.0.borrow(), |node| &node.value)
+    }
+
+    pub fn value_mut(&self) -> RefMut<'_, T> {
+        RefMut::map(self.0.borrow_mut(), |node| &mut node.value)
+    }
+
+    /// Whether both handles point to the same node.
+    pub fn ptr_eq(&self, other: &NodeRef<T>) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+
+    pub fn parent(&self) -> Option<NodeRef<T>> {
+        self.0.borrow().parent.upgrade().map(NodeRef)
+    }
+
+    pub fn children(&self) -> Vec<NodeRef<T>> {
+        self.0.borrow().children.clone()
+    }
+
+    /// The topmost ancestor, or this node if it has no parent.
+    pub fn root(&self) -> NodeRef<T> {
+        self.ancestors().last().unwrap_or_else(|| self.clone())
+    }
+
+    /// The number of ancestors.
+    pub fn depth(&self) -> usize {
+        self.ancestors().count()
+    }
+
+    /// Whether this node is `other` or one of its ancestors.
+    pub fn is_ancestor_of(&self, other: &NodeRef<T>) -> bool {
+        self.ptr_eq(other) || other.ancestors().any(|a| a.ptr_eq(self))
+    }
+
+    /// Appends `child`, which must not have a parent and must not be this
+    /// node or one of its ancestors.
+    pub fn add_child(&self, child: &NodeRef<T>) -> Result<(), TreeError> {
+        if child.parent().is_some() {
+            return Err(TreeError::AlreadyAttached);
+        }
+        if child.is_ancestor_of(self) {
+            return Err(TreeError::Cycle);
+        }
+        child.0.borrow_mut().parent = Rc::downgrade(&self.0);
+        self.0.borrow_mut().children.push(child.clone());
+        Ok(())
+    }
+
+    /// Creates a node for `value` and appends it.
+    pub fn push(&self, value: T) -> NodeRef<T> {
+        let child = NodeRef::new(value);
+        child.0.borrow_mut().parent = Rc::downgrade(&self.0);
+        self.0.borrow_mut().children.push(child.clone());
+        child
+    }
+
+    /// Removes this subtree from its parent. The subtree stays alive as long
+    /// as there are handles to it, and can be added somewhere else.
+    pub fn detach(&self) {
+        let parent = self.0.borrow_mut().parent.upgrade();
+        if let Some(parent) = parent {
+            parent
+                .borrow_mut()
+                .children
+                .retain(|c| !Rc::ptr_eq(&c.0, &self.0));
+        }
+        self.0.borrow_mut().parent = Weak::new();
+    }
+
+    /// Moves this subtree under `new_parent`. On error nothing changes.
+    pub fn reattach(&self, new_parent: &NodeRef<T>) -> Result<(), TreeError> {
+        if self.is_ancestor_of(new_parent) {
+            return Err(TreeError::Cycle);
+        }
+        self.detach();
+        new_parent.add_child(self)
+    }
+
+    /// This node and its descendants, depth first, parents before children.
+    pub fn dfs(&self) -> Dfs<T> {
+        Dfs {
+            stack: vec![self.clone()],
+        }
+    }
+
+    /// This node and its descendants, level by level.
+    pub fn bfs(&self) -> Bfs<T> {
+        Bfs {
+            queue: VecDeque::from([self.clone()]),
+        }
+    }
+
+    /// The parent, its parent, and so on up to the root.
+    pub fn ancestors(&self) -> Ancestors<T> {
+        Ancestors {
+            next: self.parent(),
+        }
+    }
+
+    pub fn strong_count(&self) -> usize {
+        Rc::strong_count(&self.0)
+    }
+
+    pub fn weak_count(&self) -> usize {
+        Rc::weak_count(&self.0)
+    }
+}
+
+pub struct Dfs<T> {
+    stack: Vec<NodeRef<T>>,
+}
+
+impl<T> Iterator for Dfs<T> {
+    type Item = NodeRef<T>;
+
+    fn next(&mut self) -> Option<NodeRef<T>> {
+        let node = self.stack.pop()?;
+        self.stack
+            .extend(node.0.borrow().children.iter().rev().cloned());
+        Some(node)
+    }
+}
+
+pub struct Bfs<T> {
+    queue: VecDeque<NodeRef<T>>,
+}
+
+impl<T> Iterator for Bfs<T> {
+    type Item = NodeRef<T>;
+
+    fn next(&mut self) -> Option<NodeRef<T>> {
+        let node = self.queue.pop_front()?;
+        self.queue.extend(node.0.borrow().children.iter().cloned());
+        Some(node)
+    }
+}
+
+pub struct Ancestors<T> {
+    next: Option<NodeRef<T>>,
+}
+
+impl<T> Iterator for Ancestors<T> {
+    type Item = NodeRef<T>;
+
+    fn next(&mut self) -> Option<NodeRef<T>> {
+        let node = self.next.take()?;
+        self.next = node.parent();
+        Some(node)
+    }
+}
+
+/// Weak references to every node of a tree, taken before its handles are
+/// dropped.
+///
+///     use std_library_types::tree::{LeakCheck, NodeRef};
+///
+///     let root = NodeRef::new("root");
+///     root.push("child").push("grandchild");
+///     let check = LeakCheck::new(&root);
+///     drop(root);
+///     assert!(check.report().is_clean());
+pub struct LeakCheck<T> {
+    nodes: Vec<Weak<RefCell<Node<T>>>>,
+}
+
+impl<T> LeakCheck<T> {
+    pub fn new(root: &NodeRef<T>) -> LeakCheck<T> {
+        LeakCheck {
+            nodes: root.dfs().map(|node| Rc::downgrade(&node.0)).collect(),
+        }
+    }
+
+    /// The nodes that are still alive.
+    pub fn report(&self) -> LeakReport<T> {
+        let leaks = self
+            .nodes
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|rc| Leak {
+                // not counting the references of `upgrade` and the check
+                strong: Rc::strong_count(&rc) - 1,
+                weak: Rc::weak_count(&rc) - 1,
+                node: NodeRef(rc),
+            })
+            .collect();
+        LeakReport {
+            checked: self.nodes.len(),
+            leaks,
+        }
+    }
+}
+
+/// A node that outlived its tree.
+pub struct Leak<T> {
+    pub node: NodeRef<T>,
+    /// Strong references other than `node`.
+    pub strong: usize,
+    /// Weak references other than the check's.
+    pub weak: usize,
+}
+
+pub struct LeakReport<T> {
+    /// The number of nodes in the tree when the check was taken.
+    pub checked: usize,
+    pub leaks: Vec<Leak<T>>,
+}
+
+impl<T> LeakReport<T> {
+    pub fn is_clean(&self) -> bool {
+        self.leaks.is_empty()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Display for LeakReport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} of {} nodes still alive",
+            self.leaks.len(),
+            self.checked
+        )?;
+        for leak in &self.leaks {
+            writeln!(
+                f,
+                "  {:?}: strong {}, weak {}",
+                leak.node.value(),
+                leak.strong,
+                leak.weak
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // root
+    // ├── a
+    // │   ├── a1
+    // │   └── a2
+    // └── b
+    //     └── b1
+    fn tree() -> NodeRef<&'static str> {
+        let root = NodeRef::new("root");
+        let a = root.push("a");
+        a.push("a1");
+        a.push("a2");
+        root.push("b").push("b1");
+        root
+    }
+
+    fn values<I: Iterator<Item = NodeRef<&'static str>>>(nodes: I) -> Vec<&'static str> {
+        nodes.map(|n| *n.value()).collect()
+    }
+
+    fn find(root: &NodeRef<&'static str>, value: &str) -> NodeRef<&'static str> {
+        root.dfs().find(|n| *n.value() == value).unwrap()
+    }
+
+    #[test]
+    fn traverses() {
+        let root = tree();
+        assert_eq!(values(root.dfs()), ["root", "a", "a1", "a2", "b", "b1"]);
+        assert_eq!(values(root.bfs()), ["root", "a", "b", "a1", "a2", "b1"]);
+        let a2 = find(&root, "a2");
+        assert_eq!(values(a2.ancestors()), ["a", "root"]);
+        assert_eq!(a2.depth(), 2);
+        assert!(a2.root().ptr_eq(&root));
+        assert!(root.is_ancestor_of(&a2));
+        assert!(!a2.is_ancestor_of(&root));
+    }
+
+    #[test]
+    fn detaches_and_reattaches() {
+        let root = tree();
+        let a = find(&root, "a");
+        a.detach();
+        assert!(a.parent().is_none());
+        assert_eq!(values(root.dfs()), ["root", "b", "b1"]);
+        assert_eq!(values(a.dfs()), ["a", "a1", "a2"]);
+
+        let b1 = find(&root, "b1");
+        a.reattach(&b1).unwrap();
+        assert_eq!(values(root.dfs()), ["root", "b", "b1", "a", "a1", "a2"]);
+        let a1 = find(&root, "a1");
+        assert_eq!(values(a1.ancestors()), ["a", "b1", "b", "root"]);
+        *a1.value_mut() = "first";
+        assert_eq!(*find(&root, "first").value(), "first");
+    }
+
+    #[test]
+    fn refuses_cycles() {
+        let root = tree();
+        let a = find(&root, "a");
+        let a1 = find(&root, "a1");
+        assert_eq!(a.reattach(&a1), Err(TreeError::Cycle));
+        assert_eq!(a.reattach(&a), Err(TreeError::Cycle));
+        assert_eq!(a1.add_child(&root), Err(TreeError::Cycle));
+        assert_eq!(root.add_child(&a1), Err(TreeError::AlreadyAttached));
+        // nothing moved
+        assert_eq!(values(root.dfs()), ["root", "a", "a1", "a2", "b", "b1"]);
+    }
+
+    #[test]
+    fn counts_references() {
+        let root = tree();
+        let a = find(&root, "a");
+        // the parent's child list and `a`
+        assert_eq!(a.strong_count(), 2);
+        // the parent links of a1 and a2
+        assert_eq!(a.weak_count(), 2);
+        drop(root);
+        assert!(a.parent().is_none());
+        assert_eq!(a.strong_count(), 1);
+    }
+
+    #[test]
+    fn reports_leaks() {
+        let root = tree();
+        let check = LeakCheck::new(&root);
+        let kept = find(&root, "a2");
+        drop(root);
+        let report = check.report();
+        assert_eq!(report.checked, 6);
+        assert_eq!(values(report.leaks.iter().map(|l| l.node.clone())), ["a2"]);
+        assert_eq!((report.leaks[0].strong, report.leaks[0].weak), (1, 0));
+        assert_eq!(
+            report.to_string(),
+            "1 of 6 nodes still alive\n  \"a2\": strong 1, weak 0\n"
+        );
+        drop(report);
+        drop(kept);
+        assert!(check.report().is_clean());
+    }
+
+    #[test]
+    fn finds_cycles_through_values() {
+        // a value holding a strong reference to an ancestor
+        struct Section {
+            link: Option<NodeRef<Section>>,
+        }
+        let root = NodeRef::new(Section { link: None });
+        let child = root.push(Section { link: None });
+        child.value_mut().link = Some(root.clone());
+        let check = LeakCheck::new(&root);
+        drop(child);
+        drop(root);
+        let report = check.report();
+        assert_eq!(report.leaks.len(), 2);
+        // breaking the cycle frees the tree
+        for leak in &report.leaks {
+            leak.node.value_mut().link = None;
+        }
+        drop(report);
+        assert!(check.report().is_clean());
+    }
+}
@@ -0,0 +1,523 @@
+// Actors
+//
+// A small actor system on top of the channels of section 03. An actor owns
+// its state and handles one message at a time from its mailbox; other threads
+// talk to it through an `Addr`, a cloneable sender.
+//
+//   - Requests carry a `Reply`, the sending half of a one-off channel, and
+//     `Addr::ask` waits for the answer.
+//   - A panic while handling a message is caught, and the actor is replaced
+//     by a fresh one from its factory, up to `Options::max_restarts` times.
+//     The message that caused the panic is dropped, and so is its `Reply`.
+//   - The actor stops once every `Addr` is dropped and its mailbox is empty.
+//   - A bounded mailbox (`Options::bounded`) is a `sync_channel`: `send`
+//     blocks while it is full, `try_send` fails.
+//   - `test` runs an actor on the calling thread, one message per `step`, so
+//     tests decide exactly when each message is handled.
+
+use std::error;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TryRecvError, TrySendError};
+use std::thread::{self, JoinHandle};
+
+pub trait Actor: Send + 'static {
+    type Message: Send + 'static;
+
+    fn handle(&mut self, message: Self::Message);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActorError {
+    /// The actor has stopped and its mailbox is closed.
+    Stopped,
+    /// The bounded mailbox is full.
+    Full,
+    /// The actor dropped the `Reply` without answering, usually because it
+    /// panicked while handling the request.
+    NoReply,
+}
+
+impl fmt::Display for ActorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActorError::Stopped => write!(f, "the actor has stopped"),
+            ActorError::Full => write!(f, "the mailbox is full"),
+            ActorError::NoReply => write!(f, "the actor did not reply"),
+        }
+    }
+}
+
+impl error::Error for ActorError {}
+
+/// How to answer a request.
+#[derive(Debug)]
+pub struct Reply<R>(Sender<R>);
+
+impl<R> Reply<R> {
+    /// Sends the answer. It is dropped if the requester stopped waiting.
+    pub fn send(self, value: R) {
+        let _ = self.0.send(value);
+    }
+}
+
+/// An answer that hasn't been waited for yet.
+#[derive(Debug)]
+pub struct Pending<R>(Receiver<R>);
+
+impl<R> Pending<R> {
+    /// Blocks until the answer arrives.
+    pub fn wait(self) -> Result<R, ActorError> {
+        self.0.recv().map_err(|_| ActorError::NoReply)
+    }
+
+    /// The answer if it has arrived; `Ok(None)` if it may still come.
+    pub fn try_take(&self) -> Result<Option<R>, ActorError> {
+        match self.0.try_recv() {
+            Ok(value) => Ok(Some(value)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(ActorError::NoReply),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Mailbox<M> {
+    Unbounded(Sender<M>),
+    Bounded(SyncSender<M>),
+}
+
+/// The address of an actor, used to send it messages.
+#[derive(Debug)]
+pub struct Addr<M> {
+    mailbox: Mailbox<M>,
+}
+
+impl<M> Clone for Addr<M> {
+    fn clone(&self) -> Self {
+        let mailbox = match &self.mailbox {
+            Mailbox::Unbounded(sender) => Mailbox::Unbounded(sender.clone()),
+            Mailbox::Bounded(sender) => Mailbox::Bounded(sender.clone()),
+        };
+        Addr { mailbox }
+    }
+}
+
+impl<M> Addr<M> {
+    /// Queues `message`, blocking while a bounded mailbox is full.
+    pub fn send(&self, message: M) -> Result<(), ActorError> {
+        let sent = match &self.mailbox {
+            Mailbox::Unbounded(sender) => sender.send(message),
+            Mailbox::Bounded(sender) => sender.send(message),
+        };
+        sent.map_err(|_| ActorError::Stopped)
+    }
+
+    /// Queues `message` if there is room, handing it back if not.
+    pub fn try_send(&self, message: M) -> Result<(), (ActorError, M)> {
+        let sent = match &self.mailbox {
+            Mailbox::Unbounded(sender) => sender
+                .send(message)
+                .map_err(|e| TrySendError::Disconnected(e.0)),
+            Mailbox::Bounded(sender) => sender.try_send(message),
+        };
+        sent.map_err(|e| match e {
+            TrySendError::Full(message) => (ActorError::Full, message),
+            TrySendError::Disconnected(message) => (ActorError::Stopped, message),
+        })
+    }
+
+    /// Sends the request built by `request` around a new `Reply`, without
+    /// waiting for the answer.
+    pub fn request<R>(
+        &self,
+        request: impl FnOnce(Reply<R>) -> M,
+    ) -> Result<Pending<R>, ActorError> {
+        let (sender, receiver) = mpsc::channel();
+        self.send(request(Reply(sender)))?;
+        Ok(Pending(receiver))
+    }
+
+    /// Sends a request and waits for the answer. Don't use it in `test`
+    /// mode, where nothing handles the request while this thread waits; use
+    /// `request`, `step` and then `Pending::wait` instead.
+    pub fn ask<R>(&self, request: impl FnOnce(Reply<R>) -> M) -> Result<R, ActorError> {
+        self.request(request)?.wait()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// The mailbox capacity, or `None` for an unbounded mailbox.
+    pub capacity: Option<usize>,
+    pub max_restarts: usize,
+}
+
+impl Default for Options {
+    /// An unbounded mailbox and up to three restarts.
+    fn default() -> Options {
+        Options {
+            capacity: None,
+            max_restarts: 3,
+        }
+    }
+}
+
+impl Options {
+    pub fn bounded(self, capacity: usize) -> Options {
+        Options {
+            capacity: Some(capacity),
+            ..self
+        }
+    }
+
+    pub fn max_restarts(self, max_restarts: usize) -> Options {
+        Options {
+            max_restarts,
+            ..self
+        }
+    }
+
+    fn mailbox<M>(&self) -> (Addr<M>, Receiver<M>) {
+        let (mailbox, receiver) = match self.capacity {
+            None => {
+                let (sender, receiver) = mpsc::channel();
+                (Mailbox::Unbounded(sender), receiver)
+            }
+            Some(capacity) => {
+                let (sender, receiver) = mpsc::sync_channel(capacity);
+                (Mailbox::Bounded(sender), receiver)
+            }
+        };
+        (Addr { mailbox }, receiver)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitReason {
+    /// Every `Addr` was dropped and the mailbox drained.
+    Shutdown,
+    /// The actor panicked once more after `max_restarts` restarts.
+    GaveUp,
+}
+
+/// How an actor ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Exit {
+    /// Messages handled, including the ones that panicked.
+    pub handled: usize,
+    pub restarts: usize,
+    pub reason: ExitReason,
+}
+
+// An actor with the means to replace it. Shared by the threaded and the
+// test mode, so both supervise the same way.
+struct Supervised<A> {
+    actor: A,
+    factory: Box<dyn Fn() -> A + Send>,
+    max_restarts: usize,
+    handled: usize,
+    restarts: usize,
+}
+
+impl<A: Actor> Supervised<A> {
+    fn new<F: Fn() -> A + Send + 'static>(factory: F, options: &Options) -> Supervised<A> {
+        Supervised {
+            actor: factory(),
+            factory: Box::new(factory),
+            max_restarts: options.max_restarts,
+            handled: 0,
+            restarts: 0,
+        }
+    }
+
+    /// Handles `message`, returning `false` if the actor gave up.
+    fn deliver(&mut self, message: A::Message) -> bool {
+        self.handled += 1;
+        let actor = &mut self.actor;
+        if panic::catch_unwind(AssertUnwindSafe(|| actor.handle(message))).is_ok() {
+            return true;
+        }
+        if self.restarts == self.max_restarts {
+            return false;
+        }
+        self.restarts += 1;
+        self.actor = (self.factory)();
+        true
+    }
+
+    fn exit(&self, reason: ExitReason) -> Exit {
+        Exit {
+            handled: self.handled,
+            restarts: self.restarts,
+            reason,
+        }
+    }
+}
+
+/// The thread running an actor.
+#[derive(Debug)]
+pub struct ActorHandle {
+    thread: JoinHandle<Exit>,
+}
+
+impl ActorHandle {
+    /// Waits for the actor to stop. Fails only if the factory panicked.
+    pub fn join(self) -> thread::Result<Exit> {
+        self.thread.join()
+    }
+}
+
+/// Starts an actor built by `factory` on a new thread. `factory` is called
+/// again for every restart.
+///
+///     use std_misc::actor::{self, Actor, Options, Reply};
+///
+///     enum Counter {
+///         Add(u64),
+///         Get(Reply<u64>),
+///     }
+///
+///     struct Total(u64);
+///
+///     impl Actor for Total {
+///         type Message = Counter;
+///
+///         fn handle(&mut self, message: Counter) {
+///             match message {
+///                 Counter::Add(n) => self.0 += n,
+///                 Counter::Get(reply) => reply.send(self.0),
+///             }
+///         }
+///     }
+///
+///     let (addr, handle) = actor::spawn(|| Total(0), Options::default());
+///     addr.send(Counter::Add(2)).unwrap();
+///     assert_eq!(addr.ask(Counter::Get).unwrap(), 2);
+///     drop(addr);
+///     assert_eq!(handle.join().unwrap().handled, 2);
+pub fn spawn<A, F>(factory: F, options: Options) -> (Addr<A::Message>, ActorHandle)
+where
+    A: Actor,
+    F: Fn() -> A + Send + 'static,
+{
+    let (addr, receiver) = options.mailbox();
+    let thread = thread::spawn(move || {
+        let mut supervised = Supervised::new(factory, &options);
+        for message in receiver {
+            if !supervised.deliver(message) {
+                return supervised.exit(ExitReason::GaveUp);
+            }
+        }
+        supervised.exit(ExitReason::Shutdown)
+    });
+    (addr, ActorHandle { thread })
+}
+
+/// An actor run by hand on the calling thread, for deterministic tests.
+pub struct TestHarness<A: Actor> {
+    supervised: Supervised<A>,
+    // `None` once the actor gave up, which closes the mailbox
+    receiver: Option<Receiver<A::Message>>,
+}
+
+/// Creates an actor that only handles messages when told to.
+pub fn test<A, F>(factory: F, options: Options) -> (Addr<A::Message>, TestHarness<A>)
+where
+    A: Actor,
+    F: Fn() -> A + Send + 'static,
+{
+    let (addr, receiver) = options.mailbox();
+    let harness = TestHarness {
+        supervised: Supervised::new(factory, &options),
+        receiver: Some(receiver),
+    };
+    (addr, harness)
+}
+
+impl<A: Actor> TestHarness<A> {
+    /// Handles the next queued message, if there is one.
+    pub fn step(&mut self) -> bool {
+        let message = match self.receiver.as_ref().map(Receiver::try_recv) {
+            Some(Ok(message)) => message,
+            _ => return false,
+        };
+        if !self.supervised.deliver(message) {
+            self.receiver = None;
+        }
+        true
+    }
+
+    /// Handles queued messages until there are none, returning how many.
+    pub fn run_until_idle(&mut self) -> usize {
+        let mut handled = 0;
+        while self.step() {
+            handled += 1;
+        }
+        handled
+    }
+
+    /// The current actor, to inspect its state.
+    pub fn actor(&self) -> &A {
+        &self.supervised.actor
+    }
+
+    pub fn restarts(&self) -> usize {
+        self.supervised.restarts
+    }
+
+    /// Handles what is queued and stops the actor.
+    pub fn finish(mut self) -> Exit {
+        self.run_until_idle();
+        match self.receiver {
+            Some(_) => self.supervised.exit(ExitReason::Shutdown),
+            None => self.supervised.exit(ExitReason::GaveUp),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::Sender;
+
+    enum Message {
+        Push(i32),
+        Sum(Reply<i32>),
+        Panic,
+        // panics without answering
+        Fail(Reply<i32>),
+        // tells the test it has started, then waits for the go-ahead
+        Block(Sender<()>, Receiver<()>),
+    }
+
+    #[derive(Default)]
+    struct Summer(Vec<i32>);
+
+    impl Actor for Summer {
+        type Message = Message;
+
+        fn handle(&mut self, message: Message) {
+            match message {
+                Message::Push(n) => self.0.push(n),
+                Message::Sum(reply) => reply.send(self.0.iter().sum()),
+                Message::Panic => panic!("asked to panic"),
+                Message::Fail(_reply) => panic!("asked to fail"),
+                Message::Block(started, go) => {
+                    started.send(()).unwrap();
+                    go.recv().unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn answers_requests_across_threads() {
+        let (addr, handle) = spawn(Summer::default, Options::default());
+        let mut senders = Vec::new();
+        for n in 1..=3 {
+            let addr = addr.clone();
+            senders.push(thread::spawn(move || addr.send(Message::Push(n)).unwrap()));
+        }
+        for sender in senders {
+            sender.join().unwrap();
+        }
+        assert_eq!(addr.ask(Message::Sum), Ok(6));
+        drop(addr);
+        let exit = handle.join().unwrap();
+        assert_eq!(
+            exit,
+            Exit {
+                handled: 4,
+                restarts: 0,
+                reason: ExitReason::Shutdown
+            }
+        );
+    }
+
+    #[test]
+    fn restarts_after_a_panic() {
+        let (addr, handle) = spawn(Summer::default, Options::default().max_restarts(1));
+        addr.send(Message::Push(5)).unwrap();
+        addr.send(Message::Panic).unwrap();
+        // the restarted actor starts from scratch
+        assert_eq!(addr.ask(Message::Sum), Ok(0));
+        addr.send(Message::Panic).unwrap();
+        let exit = handle.join().unwrap();
+        assert_eq!(exit.reason, ExitReason::GaveUp);
+        assert_eq!(exit.restarts, 1);
+        assert_eq!(addr.send(Message::Push(1)), Err(ActorError::Stopped));
+        assert_eq!(addr.ask(Message::Sum), Err(ActorError::Stopped));
+    }
+
+    #[test]
+    fn bounded_mailboxes_push_back() {
+        let (addr, handle) = spawn(Summer::default, Options::default().bounded(1));
+        let (started_tx, started) = mpsc::channel();
+        let (go, go_rx) = mpsc::channel();
+        addr.send(Message::Block(started_tx, go_rx)).unwrap();
+        started.recv().unwrap();
+        // the actor is busy, so one message fills the mailbox
+        addr.send(Message::Push(1)).unwrap();
+        match addr.try_send(Message::Push(2)) {
+            Err((ActorError::Full, Message::Push(2))) => {}
+            _ => panic!("the mailbox should be full"),
+        }
+        go.send(()).unwrap();
+        addr.send(Message::Push(2)).unwrap();
+        assert_eq!(addr.ask(Message::Sum), Ok(3));
+        drop(addr);
+        assert_eq!(handle.join().unwrap().handled, 4);
+    }
+
+    #[test]
+    fn test_mode_is_step_by_step() {
+        let (addr, mut harness) = test(Summer::default, Options::default().bounded(2));
+        addr.send(Message::Push(1)).unwrap();
+        addr.send(Message::Push(2)).unwrap();
+        assert!(matches!(
+            addr.try_send(Message::Push(3)),
+            Err((ActorError::Full, _))
+        ));
+        assert!(harness.step());
+        assert_eq!(harness.actor().0, [1]);
+        let sum = addr.request(Message::Sum).unwrap();
+        assert_eq!(sum.try_take(), Ok(None));
+        assert_eq!(harness.run_until_idle(), 2);
+        assert_eq!(sum.wait(), Ok(3));
+        assert!(!harness.step());
+    }
+
+    #[test]
+    fn test_mode_supervises() {
+        let (addr, mut harness) = test(Summer::default, Options::default());
+        addr.send(Message::Push(4)).unwrap();
+        let before = addr.request(Message::Sum).unwrap();
+        addr.send(Message::Panic).unwrap();
+        let after = addr.request(Message::Sum).unwrap();
+        assert_eq!(harness.run_until_idle(), 4);
+        assert_eq!(harness.restarts(), 1);
+        assert_eq!((before.wait(), after.wait()), (Ok(4), Ok(0)));
+
+        let failed = addr.request(Message::Fail).unwrap();
+        harness.run_until_idle();
+        assert_eq!(failed.wait(), Err(ActorError::NoReply));
+        assert_eq!(
+            harness.finish(),
+            Exit {
+                handled: 5,
+                restarts: 2,
+                reason: ExitReason::Shutdown
+            }
+        );
+    }
+
+    #[test]
+    fn test_mode_gives_up() {
+        let (addr, mut harness) = test(Summer::default, Options::default().max_restarts(0));
+        addr.send(Message::Panic).unwrap();
+        assert!(harness.step());
+        assert_eq!(addr.send(Message::Push(1)), Err(ActorError::Stopped));
+        assert_eq!(harness.finish().reason, ExitReason::GaveUp);
+    }
+}
@@ -0,0 +1,7 @@
+// Std misc library
+//
+// Reusable versions of the examples in `main.rs`.
+
+pub mod actor;
+pub mod paths;
+pub mod pool;
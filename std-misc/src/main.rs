@@ -18,9 +18,37 @@ fn main() {
     }
 
     for child in children {
-        // wait for the thread to finish. return a result.
-        let _ = child.join();
+        // wait for the thread to finish. return a result, which is an `Err`
+        // if the thread panicked.
+        if let Err(e) = child.join() {
+            println!("a thread panicked: {:?}", e);
+        }
+    }
+
+    // Instead of a thread per task, `std_misc::pool::ThreadPool` runs tasks
+    // on a fixed number of threads. A task that panics is reported by its
+    // handle rather than lost.
+    use std_misc::pool::ThreadPool;
+
+    let pool = ThreadPool::new(4);
+    let handles: Vec<_> = (0..NTHREAD)
+        .map(|i| {
+            pool.submit(move || {
+                if i == 7 {
+                    panic!("task {} failed", i);
+                }
+                format!("this is task number: {}", i)
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        match handle.join() {
+            Ok(message) => println!("{}", message),
+            Err(e) => println!("{}", e),
+        }
     }
+    println!("{:?}", pool.metrics());
 }
 */
 
@@ -167,6 +195,46 @@ fn main() {
     for id in ids {
         println!("id: {}", id.unwrap());
     }
+
+    // `std_misc::actor` builds on the same channels: an actor owns a receiver,
+    // and the senders are its address. Here the ids are collected by one.
+    use std_misc::actor::{self, Actor, Options, Reply};
+
+    enum Ids {
+        Add(i32),
+        Sorted(Reply<Vec<i32>>),
+    }
+
+    #[derive(Default)]
+    struct Collector(Vec<i32>);
+
+    impl Actor for Collector {
+        type Message = Ids;
+
+        fn handle(&mut self, message: Ids) {
+            match message {
+                Ids::Add(id) => self.0.push(id),
+                Ids::Sorted(reply) => {
+                    self.0.sort();
+                    reply.send(self.0.clone());
+                }
+            }
+        }
+    }
+
+    let (addr, collector) = actor::spawn(Collector::default, Options::default().bounded(1));
+    let children: Vec<_> = (0..NTHREADS)
+        .map(|id| {
+            let addr = addr.clone();
+            thread::spawn(move || addr.send(Ids::Add(id)).unwrap())
+        })
+        .collect();
+    for child in children {
+        child.join().expect("oops! the child thread panicked");
+    }
+    println!("ids: {:?}", addr.ask(Ids::Sorted));
+    drop(addr);
+    println!("{:?}", collector.join());
 }
 */
 
@@ -188,6 +256,7 @@ fn main() {
 // However, a `Path` can be freely converted to an `OsString` or `&OsStr` using `into_os_string` and `as_os_str`, respectively.
 
 use std::path::Path;
+use std_misc::paths::{self, Glob};
 
 fn main() {
     // create a `path` from an `&'static str`
@@ -202,12 +271,23 @@ fn main() {
     new_path.push("c");
     new_path.push("myfile.tar.gz");
 
+    // `stem` and `extensions` see every extension, not just the last
+    println!("stem {:?}, extensions {:?}", paths::stem(&new_path), paths::extensions(&new_path));
+    println!("matches a/b/?/my*.gz: {}", Glob::new("a/b/?/my*.gz").unwrap().matches(&new_path));
+    println!("normalized: {}", paths::normalize(&new_path.join("../d/./e")).display());
+    println!("from a/x: {:?}", paths::relative_to(&new_path, Path::new("a/x")));
+
+    // `safe_join` refuses untrusted paths, like archive entries, that leave the base
+    println!("{:?}", paths::safe_join(Path::new("out"), Path::new("docs/../README")));
+    println!("{:?}", paths::safe_join(Path::new("out"), Path::new("docs/../../.bashrc")));
+
     // `set_file_name` updates the file name of the `PathBuf`
     new_path.set_file_name("package.tgz");
 
-    match new_path.to_str() {
-        None => panic!("new path is not a valid UTF-8 sequence"),
-        Some(s) => println!("new path is {}", s),
+    // `to_str` fails on paths that aren't UTF-8; `display` replaces the invalid bytes instead
+    match paths::to_utf8(&new_path) {
+        Err(e) => println!("{}, shown lossily as {}", e, new_path.display()),
+        Ok(s) => println!("new path is {}", s),
     }
 }
 */
@@ -0,0 +1,410 @@
+// Paths
+//
+// Lexical path utilities for section 04. Nothing here touches the file
+// system: `..` is resolved against the components written in the path, not
+// against symlinks. Paths that aren't valid UTF-8 are handled as `OsStr`s
+// rather than rejected.
+
+use std::error;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    /// An absolute path where a relative one was needed.
+    Absolute(PathBuf),
+    /// A relative path that leads out of its base directory.
+    EscapesBase(PathBuf),
+    NotUtf8(PathBuf),
+    BadPattern {
+        pattern: String,
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathError::Absolute(path) => write!(f, "{} is absolute", path.display()),
+            PathError::EscapesBase(path) => {
+                write!(f, "{} leads outside the base directory", path.display())
+            }
+            PathError::NotUtf8(path) => write!(f, "{} is not valid UTF-8", path.display()),
+            PathError::BadPattern { pattern, reason } => write!(f, "{:?}: {}", pattern, reason),
+        }
+    }
+}
+
+impl error::Error for PathError {}
+
+/// `path` as a `&str`, or an error naming the path.
+pub fn to_utf8(path: &Path) -> Result<&str, PathError> {
+    path.to_str()
+        .ok_or_else(|| PathError::NotUtf8(path.to_path_buf()))
+}
+
+/// Removes `.` components, and `..` components along with the component
+/// before them. A relative path keeps the `..`s it can't resolve; an absolute
+/// one drops them, as `/..` is `/`. An empty result is `.`.
+///
+///     use std::path::Path;
+///     use std_misc::paths;
+///
+///     assert_eq!(paths::normalize(Path::new("a/./b/../c")), Path::new("a/c"));
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut components: Vec<Component> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match components.last() {
+                Some(Component::Normal(_)) => {
+                    components.pop();
+                }
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                Some(Component::ParentDir) | None => components.push(component),
+                Some(Component::CurDir) => unreachable!(),
+            },
+            _ => components.push(component),
+        }
+    }
+    if components.is_empty() {
+        return PathBuf::from(".");
+    }
+    components.iter().collect()
+}
+
+/// The path that leads from the directory `base` to `path`, after
+/// normalising both. `None` if only one of them is absolute, if they are on
+/// different drives, or if `base` goes up through a `..` that `path` doesn't,
+/// as the name of the directory it leads to is unknown.
+///
+///     use std::path::Path;
+///     use std_misc::paths;
+///
+///     let relative = paths::relative_to(Path::new("/srv/www/a.txt"), Path::new("/srv/log"));
+///     assert_eq!(relative.unwrap(), Path::new("../www/a.txt"));
+pub fn relative_to(path: &Path, base: &Path) -> Option<PathBuf> {
+    let path = normalize(path);
+    let base = normalize(base);
+    if path.has_root() != base.has_root() {
+        return None;
+    }
+    let is_real = |c: &Component| *c != Component::CurDir;
+    let path: Vec<Component> = path.components().filter(is_real).collect();
+    let base: Vec<Component> = base.components().filter(is_real).collect();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    if base[common..]
+        .iter()
+        .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return None;
+    }
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    relative.extend(&path[common..]);
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    Some(relative)
+}
+
+// The file name split at each `.`, with a leading `.` kept on the stem, as
+// in `.bashrc`. Empty parts are kept, so that `a..b` has an empty extension.
+fn split_name(name: &OsStr) -> Vec<&OsStr> {
+    let bytes = name.as_encoded_bytes();
+    let start = usize::from(bytes.first() == Some(&b'.'));
+    let mut parts = Vec::new();
+    let mut from = 0;
+    for (i, &b) in bytes.iter().enumerate().skip(start) {
+        if b == b'.' {
+            parts.push(&bytes[from..i]);
+            from = i + 1;
+        }
+    }
+    parts.push(&bytes[from..]);
+    parts
+        .into_iter()
+        // SAFETY: the bytes come from an `OsStr` and are split only next to
+        // an ASCII `.`, which `from_encoded_bytes_unchecked` allows.
+        .map(|part| unsafe { OsStr::from_encoded_bytes_unchecked(part) })
+        .collect()
+}
+
+/// The file name up to its first extension: `myfile` for `myfile.tar.gz`.
+/// Unlike `Path::file_stem`, which stops at the last one.
+pub fn stem(path: &Path) -> Option<&OsStr> {
+    path.file_name().map(|name| split_name(name)[0])
+}
+
+/// All the extensions of the file name, in order: `["tar", "gz"]` for
+/// `myfile.tar.gz`.
+///
+///     use std::path::Path;
+///     use std_misc::paths;
+///
+///     assert_eq!(paths::extensions(Path::new("myfile.tar.gz")), ["tar", "gz"]);
+pub fn extensions(path: &Path) -> Vec<&OsStr> {
+    match path.file_name() {
+        Some(name) => split_name(name).split_off(1),
+        None => Vec::new(),
+    }
+}
+
+/// Replaces all the extensions of the file name. Does nothing if there is
+/// no file name.
+pub fn set_extensions<S: AsRef<OsStr>>(path: &mut PathBuf, extensions: &[S]) {
+    let mut name: OsString = match stem(path) {
+        Some(stem) => stem.to_os_string(),
+        None => return,
+    };
+    for extension in extensions {
+        name.push(".");
+        name.push(extension);
+    }
+    path.set_file_name(name);
+}
+
+/// Joins an untrusted relative path, such as an archive entry, onto `base`,
+/// refusing paths that are absolute or lead outside `base`. The check is
+/// lexical, so symlinks inside `base` can still lead elsewhere.
+///
+///     use std::path::Path;
+///     use std_misc::paths;
+///
+///     let out = Path::new("/tmp/out");
+///     assert_eq!(paths::safe_join(out, Path::new("a/../b")).unwrap(), Path::new("/tmp/out/b"));
+///     assert!(paths::safe_join(out, Path::new("a/../../etc/passwd")).is_err());
+///     assert!(paths::safe_join(out, Path::new("/etc/passwd")).is_err());
+pub fn safe_join(base: &Path, untrusted: &Path) -> Result<PathBuf, PathError> {
+    if untrusted.has_root() || matches!(untrusted.components().next(), Some(Component::Prefix(_))) {
+        return Err(PathError::Absolute(untrusted.to_path_buf()));
+    }
+    let relative = normalize(untrusted);
+    if relative.starts_with("..") {
+        return Err(PathError::EscapesBase(untrusted.to_path_buf()));
+    }
+    Ok(base.join(relative))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// `**`
+    AnyDepth,
+    Pattern(Vec<char>),
+}
+
+/// A glob pattern, matched against whole paths.
+///
+/// Segments are separated by `/`. In a segment, `*` matches any characters
+/// and `?` one character; a segment of just `**` matches any number of
+/// components, including none. A leading `/` anchors the pattern at the
+/// root. Characters that aren't valid UTF-8 match `*` and `?` like any
+/// other.
+///
+///     use std::path::Path;
+///     use std_misc::paths::Glob;
+///
+///     let glob = Glob::new("src/**/*.rs").unwrap();
+///     assert!(glob.matches(Path::new("src/a/b/lib.rs")));
+///     assert!(!glob.matches(Path::new("tests/lib.rs")));
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    segments: Vec<Segment>,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Result<Glob, PathError> {
+        let bad = |reason| PathError::BadPattern {
+            pattern: pattern.to_string(),
+            reason,
+        };
+        if pattern.is_empty() {
+            return Err(bad("the pattern is empty"));
+        }
+        let mut segments = Vec::new();
+        if pattern.starts_with('/') {
+            segments.push(Segment::Pattern(Vec::new()));
+        }
+        for part in pattern.split('/').filter(|part| !part.is_empty()) {
+            if part == "**" {
+                segments.push(Segment::AnyDepth);
+            } else if part.contains("**") {
+                return Err(bad("`**` must be a whole segment"));
+            } else {
+                segments.push(Segment::Pattern(part.chars().collect()));
+            }
+        }
+        Ok(Glob { segments })
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        let names: Vec<Vec<char>> = path
+            .components()
+            .filter(|c| *c != Component::CurDir)
+            .map(|c| match c {
+                // matched by the empty segment of a leading `/`
+                Component::RootDir => Vec::new(),
+                c => c.as_os_str().to_string_lossy().chars().collect(),
+            })
+            .collect();
+        // matched[j]: the segments so far match the first `j` components
+        let mut matched = vec![false; names.len() + 1];
+        matched[0] = true;
+        for segment in &self.segments {
+            let mut next = vec![false; names.len() + 1];
+            for j in 0..=names.len() {
+                next[j] = match segment {
+                    Segment::AnyDepth => matched[j] || j > 0 && next[j - 1],
+                    Segment::Pattern(pattern) => {
+                        j > 0 && matched[j - 1] && matches_name(pattern, &names[j - 1])
+                    }
+                };
+            }
+            matched = next;
+        }
+        matched[names.len()]
+    }
+}
+
+// Wildcard matching within one component, backtracking to the last `*`.
+fn matches_name(pattern: &[char], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(path: &str) -> &Path {
+        Path::new(path)
+    }
+
+    #[test]
+    fn normalizes() {
+        assert_eq!(normalize(p("./a/b/../c/.")), p("a/c"));
+        assert_eq!(normalize(p("a/../..")), p(".."));
+        assert_eq!(normalize(p("../a/../../b")), p("../../b"));
+        assert_eq!(normalize(p("/../a/./b/..")), p("/a"));
+        assert_eq!(normalize(p("a/..")), p("."));
+        assert_eq!(normalize(p("")), p("."));
+    }
+
+    #[test]
+    fn computes_relative_paths() {
+        let relative = |path, base| relative_to(p(path), p(base));
+        assert_eq!(relative("/a/b/c", "/a/d").unwrap(), p("../b/c"));
+        assert_eq!(relative("/a/b", "/a/b").unwrap(), p("."));
+        assert_eq!(relative("/a", "/a/b/c").unwrap(), p("../.."));
+        assert_eq!(relative("a/b", ".").unwrap(), p("a/b"));
+        assert_eq!(relative("../x", "..").unwrap(), p("x"));
+        assert_eq!(relative("x", ".."), None);
+        assert_eq!(relative("/a", "a"), None);
+        assert_eq!(
+            p("/a/d").join(relative("/a/b/c", "/a/d").unwrap()),
+            p("/a/d/../b/c")
+        );
+    }
+
+    #[test]
+    fn splits_extensions() {
+        let path = p("dir.d/myfile.tar.gz");
+        assert_eq!(stem(path).unwrap(), "myfile");
+        assert_eq!(extensions(path), ["tar", "gz"]);
+        assert_eq!(path.file_stem().unwrap(), "myfile.tar");
+        assert_eq!(stem(p(".bashrc")).unwrap(), ".bashrc");
+        assert!(extensions(p(".bashrc")).is_empty());
+        assert_eq!(extensions(p(".config.tar.gz")), ["tar", "gz"]);
+        assert_eq!(extensions(p("a..b")), ["", "b"]);
+        assert!(extensions(p("/")).is_empty());
+
+        let mut path = PathBuf::from("a/b/c/myfile.tar.gz");
+        set_extensions(&mut path, &["zip"]);
+        assert_eq!(path, p("a/b/c/myfile.zip"));
+        set_extensions::<&str>(&mut path, &[]);
+        assert_eq!(path, p("a/b/c/myfile"));
+    }
+
+    #[test]
+    fn joins_safely() {
+        let base = p("/srv/archive");
+        assert_eq!(
+            safe_join(base, p("a/./b/../c.txt")).unwrap(),
+            p("/srv/archive/a/c.txt")
+        );
+        assert_eq!(safe_join(base, p("a/..")).unwrap(), p("/srv/archive/."));
+        assert_eq!(
+            safe_join(base, p("a/../../etc/passwd")),
+            Err(PathError::EscapesBase(PathBuf::from("a/../../etc/passwd")))
+        );
+        assert_eq!(
+            safe_join(base, p("/etc/passwd")),
+            Err(PathError::Absolute(PathBuf::from("/etc/passwd")))
+        );
+    }
+
+    #[test]
+    fn matches_globs() {
+        let glob = |pattern: &str, path: &str| Glob::new(pattern).unwrap().matches(p(path));
+        assert!(glob("*.rs", "main.rs"));
+        assert!(!glob("*.rs", "src/main.rs"));
+        assert!(glob("src/*.rs", "./src/main.rs"));
+        assert!(glob("src/**/*.rs", "src/main.rs"));
+        assert!(glob("src/**/*.rs", "src/a/b/c.rs"));
+        assert!(!glob("src/**/*.rs", "src/a/b/c.rs.bak"));
+        assert!(glob("**", "any/thing/at/all"));
+        assert!(glob("**/target", "target"));
+        assert!(glob("a/**/b/**/c", "a/b/x/y/c"));
+        assert!(!glob("a/**/b", "a/x/c"));
+        assert!(glob("file?.t*t", "file1.tgt"));
+        assert!(!glob("file?.txt", "file.txt"));
+        assert!(glob("*a*b*", "xaybz"));
+        assert!(!glob("*a*b", "xaybz"));
+        assert!(glob("/etc/*", "/etc/passwd"));
+        assert!(!glob("/etc/*", "etc/passwd"));
+        assert!(!glob("etc/*", "/etc/passwd"));
+        assert!(Glob::new("a**").is_err());
+        assert!(Glob::new("").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn handles_non_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = OsStr::from_bytes(b"caf\xe9.tar.gz");
+        let path = Path::new("dir").join(name);
+        assert!(matches!(to_utf8(&path), Err(PathError::NotUtf8(_))));
+        assert_eq!(stem(&path).unwrap().as_bytes(), b"caf\xe9");
+        assert_eq!(extensions(&path), ["tar", "gz"]);
+        assert!(Glob::new("dir/caf?.*.gz").unwrap().matches(&path));
+        assert_eq!(
+            safe_join(p("/base"), &path).unwrap(),
+            p("/base").join(&path)
+        );
+    }
+}
@@ -0,0 +1,536 @@
+// Thread pool
+//
+// A fixed number of worker threads taking jobs from a shared queue, in
+// place of one `thread::spawn` per task as in section 01.
+//
+//   - `submit` returns a `JoinHandle` that gives back the job's result, or
+//     the panic payload if it panicked. Workers catch panics, so one failing
+//     job neither kills its worker nor goes unnoticed.
+//   - `JoinHandle::cancel` takes a job off the queue if it hasn't started.
+//   - `scope` runs jobs that borrow from the stack. They run on as many
+//     scoped threads (`std::thread::scope`) as the pool has workers, and all
+//     of them have finished when `scope` returns.
+//   - `metrics` counts queued, running and completed jobs.
+//
+// Dropping the pool lets the workers finish the queued jobs, then joins them.
+
+use std::any::Any;
+use std::collections::VecDeque;
+use std::error;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError, Weak};
+use std::thread;
+
+// Runs the job, counts it as completed and stores its outcome, in that order,
+// so that the metrics are up to date once `join` returns.
+type Job<'a> = Box<dyn FnOnce(&Counters) + Send + 'a>;
+
+struct QueueState<'a> {
+    jobs: VecDeque<(u64, Job<'a>)>,
+    next_id: u64,
+    closed: bool,
+}
+
+// The queue of the pool, and of each scope. Jobs may borrow for `'a`.
+struct JobQueue<'a> {
+    state: Mutex<QueueState<'a>>,
+    available: Condvar,
+}
+
+impl<'a> JobQueue<'a> {
+    fn new() -> JobQueue<'a> {
+        JobQueue {
+            state: Mutex::new(QueueState {
+                jobs: VecDeque::new(),
+                next_id: 0,
+                closed: false,
+            }),
+            available: Condvar::new(),
+        }
+    }
+
+    // no job runs while the lock is held, so a poisoned state is consistent
+    fn lock(&self) -> MutexGuard<'_, QueueState<'a>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn push(&self, job: Job<'a>) -> u64 {
+        let mut state = self.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.jobs.push_back((id, job));
+        self.available.notify_one();
+        id
+    }
+
+    /// The next job, waiting for one. `None` once closed and empty.
+    fn pop(&self) -> Option<Job<'a>> {
+        let mut state = self
+            .available
+            .wait_while(self.lock(), |state| state.jobs.is_empty() && !state.closed)
+            .unwrap_or_else(PoisonError::into_inner);
+        state.jobs.pop_front().map(|(_, job)| job)
+    }
+
+    fn remove(&self, id: u64) -> bool {
+        let mut state = self.lock();
+        match state.jobs.iter().position(|(queued, _)| *queued == id) {
+            Some(index) => {
+                state.jobs.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn close(&self) {
+        self.lock().closed = true;
+        self.available.notify_all();
+    }
+}
+
+// Closes a scope's queue even if the scope's closure panics, so that its
+// workers stop and `thread::scope` can return.
+struct CloseOnDrop<'q, 'a>(&'q JobQueue<'a>);
+
+impl Drop for CloseOnDrop<'_, '_> {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    queued: AtomicUsize,
+    running: AtomicUsize,
+    completed: AtomicUsize,
+    panicked: AtomicUsize,
+    cancelled: AtomicUsize,
+}
+
+fn work(queue: &JobQueue<'_>, counters: &Counters) {
+    while let Some(job) = queue.pop() {
+        counters.queued.fetch_sub(1, Ordering::SeqCst);
+        counters.running.fetch_add(1, Ordering::SeqCst);
+        job(counters);
+    }
+}
+
+/// A snapshot of the pool's counters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metrics {
+    pub workers: usize,
+    /// Jobs waiting for a worker.
+    pub queued: usize,
+    pub running: usize,
+    /// Jobs that have finished, including those that panicked.
+    pub completed: usize,
+    pub panicked: usize,
+    pub cancelled: usize,
+}
+
+/// Why a job has no result.
+pub enum JobError {
+    /// The job panicked with this payload.
+    Panicked(Box<dyn Any + Send>),
+    Cancelled,
+}
+
+impl JobError {
+    /// The panic message, if the payload is a string.
+    pub fn panic_message(&self) -> Option<&str> {
+        match self {
+            JobError::Panicked(payload) => payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str)),
+            JobError::Cancelled => None,
+        }
+    }
+}
+
+impl fmt::Debug for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JobError::Panicked(_) => f
+                .debug_tuple("Panicked")
+                .field(&self.panic_message().unwrap_or("<non-string payload>"))
+                .finish(),
+            JobError::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self, self.panic_message()) {
+            (JobError::Panicked(_), Some(message)) => write!(f, "the job panicked: {}", message),
+            (JobError::Panicked(_), None) => write!(f, "the job panicked"),
+            (JobError::Cancelled, _) => write!(f, "the job was cancelled"),
+        }
+    }
+}
+
+impl error::Error for JobError {}
+
+struct Slot<T> {
+    outcome: Mutex<Option<Result<T, JobError>>>,
+    done: Condvar,
+}
+
+impl<T> Slot<T> {
+    fn fill(&self, outcome: Result<T, JobError>) {
+        *self.outcome.lock().unwrap_or_else(PoisonError::into_inner) = Some(outcome);
+        self.done.notify_all();
+    }
+}
+
+/// The result of a submitted job, once it has run.
+pub struct JoinHandle<T> {
+    id: u64,
+    slot: Arc<Slot<T>>,
+    // only jobs of the pool itself can be cancelled
+    queue: Option<Weak<JobQueue<'static>>>,
+    counters: Arc<Counters>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Waits for the job, returning its result or why there is none.
+    pub fn join(self) -> Result<T, JobError> {
+        let outcome = self
+            .slot
+            .done
+            .wait_while(
+                self.slot
+                    .outcome
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner),
+                |outcome| outcome.is_none(),
+            )
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        outcome.expect("the outcome is set before `done` is notified")
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.slot
+            .outcome
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_some()
+    }
+
+    /// Removes the job from the queue if it hasn't started, so that `join`
+    /// returns `JobError::Cancelled`. Returns whether it was removed. Jobs
+    /// submitted to a `Scope` always run.
+    pub fn cancel(&self) -> bool {
+        let queue = match self.queue.as_ref().and_then(Weak::upgrade) {
+            Some(queue) => queue,
+            None => return false,
+        };
+        if !queue.remove(self.id) {
+            return false;
+        }
+        self.counters.queued.fetch_sub(1, Ordering::SeqCst);
+        self.counters.cancelled.fetch_add(1, Ordering::SeqCst);
+        self.slot.fill(Err(JobError::Cancelled));
+        true
+    }
+}
+
+fn submit_to<'a, T, F>(queue: &JobQueue<'a>, counters: &Arc<Counters>, f: F) -> (u64, Arc<Slot<T>>)
+where
+    T: Send + 'a,
+    F: FnOnce() -> T + Send + 'a,
+{
+    let slot = Arc::new(Slot {
+        outcome: Mutex::new(None),
+        done: Condvar::new(),
+    });
+    let job_slot = Arc::clone(&slot);
+    let job: Job<'a> = Box::new(move |counters| {
+        let outcome = panic::catch_unwind(AssertUnwindSafe(f)).map_err(JobError::Panicked);
+        if outcome.is_err() {
+            counters.panicked.fetch_add(1, Ordering::SeqCst);
+        }
+        counters.completed.fetch_add(1, Ordering::SeqCst);
+        counters.running.fetch_sub(1, Ordering::SeqCst);
+        job_slot.fill(outcome);
+    });
+    // counted before the job is visible, so `queued` never goes below zero
+    counters.queued.fetch_add(1, Ordering::SeqCst);
+    let id = queue.push(job);
+    (id, slot)
+}
+
+/// A fixed set of workers sharing one job queue.
+///
+///     use std_misc::pool::ThreadPool;
+///
+///     let pool = ThreadPool::new(4);
+///     let squares: Vec<_> = (0..8u64).map(|i| pool.submit(move || i * i)).collect();
+///     let total: u64 = squares.into_iter().map(|h| h.join().unwrap()).sum();
+///     assert_eq!(total, 140);
+///
+///     let data = vec![1, 2, 3];
+///     let sum = pool.scope(|scope| scope.submit(|| data.iter().sum::<i32>()).join());
+///     assert_eq!(sum.unwrap(), 6);
+pub struct ThreadPool {
+    queue: Arc<JobQueue<'static>>,
+    counters: Arc<Counters>,
+    workers: Vec<thread::JoinHandle<()>>,
+}
+
+impl ThreadPool {
+    /// A pool of `size` worker threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn new(size: usize) -> ThreadPool {
+        assert!(size > 0, "a thread pool needs at least one worker");
+        let queue = Arc::new(JobQueue::new());
+        let counters = Arc::new(Counters::default());
+        let workers = (0..size)
+            .map(|i| {
+                let (queue, counters) = (Arc::clone(&queue), Arc::clone(&counters));
+                thread::Builder::new()
+                    .name(format!("pool-worker-{}", i))
+                    .spawn(move || work(&queue, &counters))
+                    .expect("failed to spawn a worker thread")
+            })
+            .collect();
+        ThreadPool {
+            queue,
+            counters,
+            workers,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Queues `f` to run on a worker.
+    pub fn submit<T, F>(&self, f: F) -> JoinHandle<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (id, slot) = submit_to(&self.queue, &self.counters, f);
+        JoinHandle {
+            id,
+            slot,
+            queue: Some(Arc::downgrade(&self.queue)),
+            counters: Arc::clone(&self.counters),
+        }
+    }
+
+    /// Runs `f` with a `Scope` for jobs that borrow from the caller, and
+    /// waits for all of them before returning.
+    pub fn scope<'env, R>(&self, f: impl FnOnce(&Scope<'_, 'env>) -> R) -> R {
+        let queue = JobQueue::new();
+        thread::scope(|s| {
+            for _ in 0..self.size() {
+                s.spawn(|| work(&queue, &self.counters));
+            }
+            let _close = CloseOnDrop(&queue);
+            f(&Scope {
+                queue: &queue,
+                counters: &self.counters,
+            })
+        })
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        let counters = &self.counters;
+        Metrics {
+            workers: self.size(),
+            queued: counters.queued.load(Ordering::SeqCst),
+            running: counters.running.load(Ordering::SeqCst),
+            completed: counters.completed.load(Ordering::SeqCst),
+            panicked: counters.panicked.load(Ordering::SeqCst),
+            cancelled: counters.cancelled.load(Ordering::SeqCst),
+        }
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        self.queue.close();
+        for worker in self.workers.drain(..) {
+            // workers catch the panics of their jobs
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Submits jobs that may borrow anything that outlives the `scope` call.
+pub struct Scope<'q, 'env> {
+    queue: &'q JobQueue<'env>,
+    counters: &'q Arc<Counters>,
+}
+
+impl<'env> Scope<'_, 'env> {
+    pub fn submit<T, F>(&self, f: F) -> JoinHandle<T>
+    where
+        T: Send + 'env,
+        F: FnOnce() -> T + Send + 'env,
+    {
+        let (id, slot) = submit_to(self.queue, self.counters, f);
+        JoinHandle {
+            id,
+            slot,
+            queue: None,
+            counters: Arc::clone(self.counters),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    // A job that reports it has started, then waits to be released.
+    fn blocker(pool: &ThreadPool) -> (JoinHandle<()>, mpsc::Sender<()>) {
+        let (started_tx, started) = mpsc::channel();
+        let (release, release_rx) = mpsc::channel::<()>();
+        let handle = pool.submit(move || {
+            started_tx.send(()).unwrap();
+            let _ = release_rx.recv();
+        });
+        started.recv().unwrap();
+        (handle, release)
+    }
+
+    #[test]
+    fn returns_results_and_panics() {
+        let pool = ThreadPool::new(2);
+        let ok = pool.submit(|| 6 * 7);
+        let failed = pool.submit(|| -> i32 { panic!("job {} failed", 2) });
+        let after = pool.submit(|| "still working");
+        assert_eq!(ok.join().unwrap(), 42);
+        let error = failed.join().unwrap_err();
+        assert_eq!(error.panic_message(), Some("job 2 failed"));
+        assert_eq!(error.to_string(), "the job panicked: job 2 failed");
+        assert_eq!(after.join().unwrap(), "still working");
+        drop(pool);
+    }
+
+    #[test]
+    fn cancels_queued_jobs() {
+        let pool = ThreadPool::new(1);
+        let (running, release) = blocker(&pool);
+        let queued = pool.submit(|| 1);
+        let kept = pool.submit(|| 2);
+        assert_eq!(
+            pool.metrics(),
+            Metrics {
+                workers: 1,
+                queued: 2,
+                running: 1,
+                completed: 0,
+                panicked: 0,
+                cancelled: 0
+            }
+        );
+        assert!(queued.cancel());
+        assert!(!queued.cancel());
+        assert!(!running.cancel());
+        assert!(queued.is_finished());
+        assert!(matches!(queued.join(), Err(JobError::Cancelled)));
+        release.send(()).unwrap();
+        assert_eq!(kept.join().unwrap(), 2);
+        running.join().unwrap();
+        let metrics = pool.metrics();
+        assert_eq!((metrics.queued, metrics.cancelled), (0, 1));
+    }
+
+    #[test]
+    fn counts_jobs() {
+        let pool = ThreadPool::new(3);
+        let handles: Vec<_> = (0..30)
+            .map(|i| {
+                pool.submit(move || {
+                    if i % 10 == 0 {
+                        panic!("every tenth job fails");
+                    }
+                    i
+                })
+            })
+            .collect();
+        let failures = handles
+            .into_iter()
+            .map(JoinHandle::join)
+            .filter(Result::is_err)
+            .count();
+        assert_eq!(failures, 3);
+        assert_eq!(
+            pool.metrics(),
+            Metrics {
+                workers: 3,
+                queued: 0,
+                running: 0,
+                completed: 30,
+                panicked: 3,
+                cancelled: 0
+            }
+        );
+    }
+
+    #[test]
+    fn finishes_queued_jobs_on_drop() {
+        let pool = ThreadPool::new(1);
+        let (running, release) = blocker(&pool);
+        let queued: Vec<_> = (0..5).map(|i| pool.submit(move || i)).collect();
+        release.send(()).unwrap();
+        drop(pool);
+        running.join().unwrap();
+        let results: Vec<i32> = queued.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(results, [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn scoped_jobs_borrow() {
+        let pool = ThreadPool::new(4);
+        let mut words = vec![
+            String::from("the"),
+            String::from("quick"),
+            String::from("fox"),
+        ];
+        let lengths: Vec<usize> = pool.scope(|scope| {
+            let handles: Vec<_> = words
+                .iter()
+                .map(|w| scope.submit(move || w.len()))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(lengths, [3, 5, 3]);
+
+        // jobs may also borrow mutably, and are done when `scope` returns
+        pool.scope(|scope| {
+            for word in words.iter_mut() {
+                let handle = scope.submit(move || word.make_ascii_uppercase());
+                assert!(!handle.cancel());
+            }
+        });
+        assert_eq!(words, ["THE", "QUICK", "FOX"]);
+        assert_eq!(pool.metrics().completed, 6);
+    }
+
+    #[test]
+    fn scope_survives_a_panicking_closure() {
+        let pool = ThreadPool::new(2);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            pool.scope(|scope| {
+                scope.submit(|| 1);
+                panic!("the scope's closure failed");
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(pool.metrics().completed, 1);
+    }
+}
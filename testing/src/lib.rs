@@ -82,10 +82,10 @@ mod tests {
 ///
 /// The next lines present detailed documentation. Code blocks start with
 /// triple backquotes and have implicit `fn main()` inside
-/// and `extern crate <cratename>`. Assume we're testing `doccomments` crate:
+/// and `extern crate <cratename>`. Assume we're testing the `adder` crate:
 ///
 /// ```
-/// let result = doccomments::add(2, 3);
+/// let result = adder::add(2, 3);
 /// assert_eq!(result, 5);
 /// ```
 pub fn add(a: i32, b: i32) -> i32 {
@@ -99,7 +99,7 @@ pub fn add(a: i32, b: i32) -> i32 {
 /// # Examples
 ///
 /// ```
-/// let result = doccomments::div(10, 2);
+/// let result = adder::div(10, 2);
 /// assert_eq!(result, 5);
 /// ```
 ///
@@ -109,7 +109,7 @@ pub fn add(a: i32, b: i32) -> i32 {
 ///
 /// ```rust,should_panic
 /// // panics on division by zero
-/// doccomments::div(10, 0);
+/// adder::div(10, 0);
 /// ```
 pub fn div(a: i32, b: i32) -> i32 {
     if b == 0 {
@@ -124,7 +124,7 @@ pub fn div(a: i32, b: i32) -> i32 {
 /// ```
 /// # // hidden lines start with `#` symbol, but they're still compilable!
 /// # fn try_main() -> Result<(), String> { // line that wraps the body shown in doc
-/// let res = doccomments::try_div(10, 2)?;
+/// let res = adder::try_div(10, 2)?;
 /// # Ok(()) // returning from try_main
 /// # }
 /// # fn main() { // starting main that'll unwrap()
@@ -153,10 +153,177 @@ pub fn add(a: i32, b: i32) -> i32 {
 // section 04. dev-dependencies
 /*
  */
+
+// The arithmetic from the sections above as one API. Every operation that can
+// fail comes in two flavours: one that panics, documented under "Panics", and
+// a `try_` one that returns a `MathError`.
+
+use std::error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathError {
+    DivideByZero,
+    /// `divide_non_zero_result` was asked for a quotient that rounds to zero.
+    ZeroResult,
+    NegativeSquareRoot,
+    Overflow,
+}
+
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MathError::DivideByZero => write!(f, "divide by zero"),
+            MathError::ZeroResult => write!(f, "divide result is zero"),
+            MathError::NegativeSquareRoot => write!(f, "negative floats don't have square roots"),
+            MathError::Overflow => write!(f, "arithmetic overflow"),
+        }
+    }
+}
+
+impl error::Error for MathError {}
+
+/// Adds two numbers.
+///
+/// ```
+/// assert_eq!(adder::add(2, 3), 5);
+/// ```
+///
+/// # Panics
+///
+/// Like `+`, panics on overflow in debug builds and wraps in release builds.
+/// `try_add` reports the overflow instead:
+///
+/// ```
+/// use adder::MathError;
+///
+/// assert_eq!(adder::try_add(i32::MAX, 1), Err(MathError::Overflow));
+/// ```
 pub fn add(a: i32, b: i32) -> i32 {
     a + b
 }
 
+/// Adds two numbers, reporting overflow.
+///
+/// ```
+/// use adder::MathError;
+///
+/// assert_eq!(adder::try_add(2, 3), Ok(5));
+/// assert_eq!(adder::try_add(i32::MAX, 1), Err(MathError::Overflow));
+/// ```
+pub fn try_add(a: i32, b: i32) -> Result<i32, MathError> {
+    a.checked_add(b).ok_or(MathError::Overflow)
+}
+
+/// Divides two numbers.
+///
+/// # Examples
+///
+/// ```
+/// let result = adder::div(10, 2);
+/// assert_eq!(result, 5);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if the second argument is zero.
+///
+/// ```rust,should_panic
+/// // panics on division by zero
+/// adder::div(10, 0);
+/// ```
+pub fn div(a: i32, b: i32) -> i32 {
+    if b == 0 {
+        panic!("Divide-by-zero error");
+    }
+
+    a / b
+}
+
+/// Divides two numbers, returning an error instead of panicking.
+///
+/// ```
+/// # fn try_main() -> Result<(), adder::MathError> {
+/// let res = adder::try_div(10, 2)?;
+/// assert_eq!(res, 5);
+/// # Ok(())
+/// # }
+/// # fn main() {
+/// #     try_main().unwrap();
+/// # }
+/// ```
+///
+/// ```
+/// assert_eq!(adder::try_div(1, 0), Err(adder::MathError::DivideByZero));
+/// assert_eq!(adder::try_div(i32::MIN, -1), Err(adder::MathError::Overflow));
+/// ```
+pub fn try_div(a: i32, b: i32) -> Result<i32, MathError> {
+    if b == 0 {
+        Err(MathError::DivideByZero)
+    } else {
+        a.checked_div(b).ok_or(MathError::Overflow)
+    }
+}
+
+/// The square root of a non-negative number.
+///
+/// ```
+/// assert_eq!(adder::sqrt(4.0), Ok(2.0));
+/// assert_eq!(adder::sqrt(-2.0), Err(adder::MathError::NegativeSquareRoot));
+/// ```
+pub fn sqrt(number: f64) -> Result<f64, MathError> {
+    if number >= 0.0 {
+        Ok(number.powf(0.5))
+    } else {
+        Err(MathError::NegativeSquareRoot)
+    }
+}
+
+/// Divides `a` by `b` when the quotient is at least one.
+///
+/// ```
+/// assert_eq!(adder::divide_non_zero_result(10, 2), 5);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `b` is zero, or if `a` is smaller than `b`.
+///
+/// ```rust,should_panic
+/// adder::divide_non_zero_result(1, 0);
+/// ```
+///
+/// ```rust,should_panic
+/// // the quotient would be zero
+/// adder::divide_non_zero_result(1, 10);
+/// ```
+pub fn divide_non_zero_result(a: u32, b: u32) -> u32 {
+    match try_divide_non_zero_result(a, b) {
+        Ok(result) => result,
+        Err(MathError::DivideByZero) => panic!("divide by zero error"),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// `divide_non_zero_result` without panicking.
+///
+/// ```
+/// use adder::MathError;
+///
+/// assert_eq!(adder::try_divide_non_zero_result(10, 2), Ok(5));
+/// assert_eq!(adder::try_divide_non_zero_result(1, 0), Err(MathError::DivideByZero));
+/// assert_eq!(adder::try_divide_non_zero_result(1, 10), Err(MathError::ZeroResult));
+/// ```
+pub fn try_divide_non_zero_result(a: u32, b: u32) -> Result<u32, MathError> {
+    if b == 0 {
+        Err(MathError::DivideByZero)
+    } else if a < b {
+        Err(MathError::ZeroResult)
+    } else {
+        Ok(a / b)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -168,7 +335,21 @@ mod tests {
     }
 
     #[test]
+    #[ignore = "deliberately wrong expectation, kept to show pretty_assertions' diff output"]
     fn test_bad_add() {
         assert_eq!(add(2, 4), 8);
     }
+
+    #[test]
+    fn test_sqrt() -> Result<(), MathError> {
+        let x = 4.0;
+        assert_eq!(sqrt(x)?.powf(2.0), x);
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "divide result is zero")]
+    fn test_specific_panic() {
+        divide_non_zero_result(1, 10);
+    }
 }
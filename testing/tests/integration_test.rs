@@ -1,7 +1,54 @@
 mod common;
 
+use adder::MathError;
+
 #[test]
 fn test_add() {
     common::setup();
     assert_eq!(adder::add(3, 2), 5);
+    assert_eq!(adder::try_add(i32::MAX, 1), Err(MathError::Overflow));
+}
+
+#[test]
+fn test_div() {
+    common::setup();
+    assert_eq!(adder::div(10, 3), 3);
+    assert_eq!(adder::try_div(10, 0), Err(MathError::DivideByZero));
+}
+
+#[test]
+#[should_panic(expected = "Divide-by-zero error")]
+fn test_div_by_zero_panics() {
+    common::setup();
+    adder::div(1, 0);
+}
+
+#[test]
+fn test_sqrt() {
+    common::setup();
+    assert_eq!(adder::sqrt(9.0), Ok(3.0));
+    assert_eq!(adder::sqrt(-1.0), Err(MathError::NegativeSquareRoot));
+}
+
+#[test]
+fn test_divide_non_zero_result() {
+    common::setup();
+    assert_eq!(adder::divide_non_zero_result(10, 2), 5);
+    assert_eq!(
+        adder::try_divide_non_zero_result(1, 10),
+        Err(MathError::ZeroResult)
+    );
+}
+
+#[test]
+#[should_panic(expected = "divide by zero error")]
+fn test_divide_non_zero_result_by_zero_panics() {
+    common::setup();
+    adder::divide_non_zero_result(1, 0);
+}
+
+#[test]
+fn test_errors_display() {
+    common::setup();
+    assert_eq!(MathError::DivideByZero.to_string(), "divide by zero");
 }
@@ -0,0 +1,633 @@
+// Cast auditing
+//
+// `as` never fails: integers wrap, floats saturate, NaN becomes 0 and
+// fractions are silently dropped. The functions here make that choice
+// explicit. A `Policy` says what to do when the value doesn't fit the target
+// type (`Overflow`) and how to deal with a fractional part or lost precision
+// (`Rounding`), and `report` explains what a plain `as` cast would do to a
+// given value.
+//
+//     use types::cast::{self, Policy, Rounding};
+//
+//     assert_eq!(cast::wrapping::<i32, u8>(1000), 232);
+//     assert_eq!(cast::saturating::<f32, u8>(300.0), 255);
+//     assert!(cast::checked::<f32, u8>(2.5).is_err());
+//     let nearest = Policy::CHECKED.rounding(Rounding::HalfEven);
+//     assert_eq!(cast::cast::<f32, u8>(2.5, nearest), Ok(2));
+
+use std::error;
+use std::fmt;
+
+use self::sealed::{Kind, Repr, Sealed};
+
+/// What to do with a value outside the range of the target type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Overflow {
+    Error,
+    /// Clamp to the nearest bound. NaN becomes 0.
+    Saturate,
+    /// Keep the low bits, like `as` between integers. NaN and infinities
+    /// become 0. Float targets overflow to infinity.
+    Wrap,
+}
+
+/// How to get rid of a fractional part, or of precision the target lacks.
+///
+/// Float targets always round to nearest unless the rounding is `Exact`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rounding {
+    /// Fail unless the target holds exactly the same value.
+    Exact,
+    /// Drop the fractional part, like `as`.
+    TowardZero,
+    /// Round to nearest, ties away from zero (`f64::round`).
+    HalfAwayFromZero,
+    /// Round to nearest, ties to even (banker's rounding).
+    HalfEven,
+    Floor,
+    Ceiling,
+}
+
+impl Rounding {
+    fn apply(self, x: f64) -> Option<f64> {
+        match self {
+            Rounding::Exact => Some(x).filter(|x| x.fract() == 0.0),
+            Rounding::TowardZero => Some(x.trunc()),
+            Rounding::HalfAwayFromZero => Some(x.round()),
+            Rounding::HalfEven => Some(x.round_ties_even()),
+            Rounding::Floor => Some(x.floor()),
+            Rounding::Ceiling => Some(x.ceil()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Policy {
+    pub overflow: Overflow,
+    pub rounding: Rounding,
+}
+
+impl Policy {
+    /// Fails whenever the value would change.
+    pub const CHECKED: Policy = Policy::new(Overflow::Error, Rounding::Exact);
+    pub const SATURATING: Policy = Policy::new(Overflow::Saturate, Rounding::TowardZero);
+    pub const WRAPPING: Policy = Policy::new(Overflow::Wrap, Rounding::TowardZero);
+
+    pub const fn new(overflow: Overflow, rounding: Rounding) -> Policy {
+        Policy { overflow, rounding }
+    }
+
+    pub const fn rounding(self, rounding: Rounding) -> Policy {
+        Policy { rounding, ..self }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CastError {
+    NotANumber { to: &'static str },
+    OutOfRange { value: String, to: &'static str },
+    Inexact { value: String, to: &'static str },
+}
+
+impl fmt::Display for CastError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CastError::NotANumber { to } => write!(f, "NaN has no {} value", to),
+            CastError::OutOfRange { value, to } => {
+                write!(f, "{} is out of range for {}", value, to)
+            }
+            CastError::Inexact { value, to } => {
+                write!(f, "{} cannot be represented exactly as {}", value, to)
+            }
+        }
+    }
+}
+
+impl error::Error for CastError {}
+
+/// The primitive numeric types. Sealed: implemented for every integer type,
+/// `f32` and `f64`.
+pub trait Primitive: Copy + fmt::Debug + fmt::Display + Sealed {
+    const NAME: &'static str;
+}
+
+mod sealed {
+    // The names are private to the crate, but they appear in the methods of
+    // a public trait, so they are declared `pub` here.
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Repr {
+        /// A negative integer.
+        Neg(i128),
+        /// A non-negative integer.
+        NonNeg(u128),
+        Float(f64),
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    pub enum Kind {
+        Int { min: i128, max: u128 },
+        Float { digits: u32, max: f64 },
+    }
+
+    pub trait Sealed {
+        const KIND: Kind;
+        fn to_repr(self) -> Repr;
+        // all three behave like `as`
+        fn from_i128(v: i128) -> Self;
+        fn from_u128(v: u128) -> Self;
+        fn from_f64(v: f64) -> Self;
+    }
+}
+
+macro_rules! primitive {
+    ($($t:ty => |$v:ident| $repr:expr, $kind:expr;)*) => {$(
+        impl Primitive for $t {
+            const NAME: &'static str = stringify!($t);
+        }
+
+        #[allow(clippy::unnecessary_cast)]
+        impl Sealed for $t {
+            const KIND: Kind = $kind;
+
+            fn to_repr(self) -> Repr {
+                let $v = self;
+                $repr
+            }
+
+            fn from_i128(v: i128) -> Self {
+                v as $t
+            }
+
+            fn from_u128(v: u128) -> Self {
+                v as $t
+            }
+
+            fn from_f64(v: f64) -> Self {
+                v as $t
+            }
+        }
+    )*};
+}
+
+macro_rules! signed {
+    ($($t:ty),*) => {
+        primitive! {$(
+            $t => |v| if v < 0 { Repr::Neg(v as i128) } else { Repr::NonNeg(v as u128) },
+                Kind::Int { min: <$t>::MIN as i128, max: <$t>::MAX as u128 };
+        )*}
+    };
+}
+
+macro_rules! unsigned {
+    ($($t:ty),*) => {
+        primitive! {$(
+            $t => |v| Repr::NonNeg(v as u128), Kind::Int { min: 0, max: <$t>::MAX as u128 };
+        )*}
+    };
+}
+
+signed!(i8, i16, i32, i64, i128, isize);
+unsigned!(u8, u16, u32, u64, u128, usize);
+primitive! {
+    f32 => |v| Repr::Float(v as f64), Kind::Float { digits: f32::MANTISSA_DIGITS, max: f32::MAX as f64 };
+    f64 => |v| Repr::Float(v), Kind::Float { digits: f64::MANTISSA_DIGITS, max: f64::MAX };
+}
+
+/// Converts `value` to `U` following `policy`.
+pub fn cast<T: Primitive, U: Primitive>(value: T, policy: Policy) -> Result<U, CastError> {
+    let repr = value.to_repr();
+    match U::KIND {
+        Kind::Int { min, max } => to_int::<T, U>(value, repr, min, max, policy),
+        Kind::Float { digits, max } => to_float::<T, U>(value, repr, digits, max, policy),
+    }
+}
+
+/// Converts `value` to `U`, failing if the value would change in any way.
+pub fn checked<T: Primitive, U: Primitive>(value: T) -> Result<U, CastError> {
+    cast(value, Policy::CHECKED)
+}
+
+/// Converts `value` to `U`, clamping to the bounds of `U`.
+pub fn saturating<T: Primitive, U: Primitive>(value: T) -> U {
+    match cast(value, Policy::SATURATING) {
+        Ok(v) => v,
+        Err(e) => unreachable!("saturating casts cannot fail: {}", e),
+    }
+}
+
+/// Converts `value` to `U`, keeping the low bits of the integer part.
+pub fn wrapping<T: Primitive, U: Primitive>(value: T) -> U {
+    match cast(value, Policy::WRAPPING) {
+        Ok(v) => v,
+        Err(e) => unreachable!("wrapping casts cannot fail: {}", e),
+    }
+}
+
+/// The same result as `value as U`.
+pub fn as_cast<T: Primitive, U: Primitive>(value: T) -> U {
+    match (T::KIND, U::KIND) {
+        (Kind::Float { .. }, Kind::Int { .. }) => saturating(value),
+        _ => wrapping(value),
+    }
+}
+
+fn to_int<T: Primitive, U: Primitive>(
+    value: T,
+    repr: Repr,
+    min: i128,
+    max: u128,
+    policy: Policy,
+) -> Result<U, CastError> {
+    let out_of_range = || CastError::OutOfRange {
+        value: value.to_string(),
+        to: U::NAME,
+    };
+    let int = match repr {
+        Repr::Float(x) if x.is_nan() => {
+            return match policy.overflow {
+                Overflow::Error => Err(CastError::NotANumber { to: U::NAME }),
+                Overflow::Saturate | Overflow::Wrap => Ok(U::from_u128(0)),
+            };
+        }
+        Repr::Float(x) => {
+            // infinities have nothing to round; they go to the overflow policy
+            let rounded = if x.is_infinite() {
+                Some(x)
+            } else {
+                policy.rounding.apply(x)
+            };
+            let rounded = match rounded {
+                Some(rounded) => rounded,
+                None => {
+                    return Err(CastError::Inexact {
+                        value: value.to_string(),
+                        to: U::NAME,
+                    })
+                }
+            };
+            match float_to_int(rounded) {
+                Some(int) => int,
+                // beyond 128 bits, out of range for every integer type
+                None => {
+                    return match policy.overflow {
+                        Overflow::Error => Err(out_of_range()),
+                        Overflow::Saturate if rounded < 0.0 => Ok(U::from_i128(min)),
+                        Overflow::Saturate => Ok(U::from_u128(max)),
+                        Overflow::Wrap => Ok(U::from_u128(wrap_float(rounded))),
+                    };
+                }
+            }
+        }
+        int => int,
+    };
+    let in_range = match int {
+        Repr::Neg(v) => v >= min,
+        Repr::NonNeg(v) => v <= max,
+        Repr::Float(_) => unreachable!(),
+    };
+    match (int, in_range, policy.overflow) {
+        (_, false, Overflow::Error) => Err(out_of_range()),
+        (Repr::Neg(_), false, Overflow::Saturate) => Ok(U::from_i128(min)),
+        (Repr::NonNeg(_), false, Overflow::Saturate) => Ok(U::from_u128(max)),
+        // `as` between integers keeps the low bits, which is exactly the
+        // value when it's in range
+        (Repr::Neg(v), _, _) => Ok(U::from_i128(v)),
+        (Repr::NonNeg(v), _, _) => Ok(U::from_u128(v)),
+        (Repr::Float(_), _, _) => unreachable!(),
+    }
+}
+
+fn to_float<T: Primitive, U: Primitive>(
+    value: T,
+    repr: Repr,
+    digits: u32,
+    max: f64,
+    policy: Policy,
+) -> Result<U, CastError> {
+    let (result, exact) = match repr {
+        Repr::Float(x) => {
+            let result = U::from_f64(x);
+            let exact = x.is_nan() || result.to_repr() == Repr::Float(x);
+            (result, exact)
+        }
+        Repr::Neg(v) => (
+            U::from_i128(v),
+            significant_bits(v.unsigned_abs()) <= digits,
+        ),
+        Repr::NonNeg(v) => (U::from_u128(v), significant_bits(v) <= digits),
+    };
+    let overflowed = match (repr, result.to_repr()) {
+        (Repr::Float(x), Repr::Float(y)) => x.is_finite() && y.is_infinite(),
+        (_, Repr::Float(y)) => y.is_infinite(),
+        _ => unreachable!(),
+    };
+    if overflowed {
+        let negative = matches!(repr, Repr::Neg(_)) || matches!(repr, Repr::Float(x) if x < 0.0);
+        return match policy.overflow {
+            Overflow::Error => Err(CastError::OutOfRange {
+                value: value.to_string(),
+                to: U::NAME,
+            }),
+            Overflow::Saturate if negative => Ok(U::from_f64(-max)),
+            Overflow::Saturate => Ok(U::from_f64(max)),
+            Overflow::Wrap => Ok(result),
+        };
+    }
+    if !exact && policy.rounding == Rounding::Exact {
+        return Err(CastError::Inexact {
+            value: value.to_string(),
+            to: U::NAME,
+        });
+    }
+    Ok(result)
+}
+
+fn significant_bits(v: u128) -> u32 {
+    if v == 0 {
+        0
+    } else {
+        128 - v.leading_zeros() - v.trailing_zeros()
+    }
+}
+
+/// `x` as an integer, if it is one and fits in 128 bits.
+fn float_to_int(x: f64) -> Option<Repr> {
+    const TWO_POW_127: f64 = (1u128 << 127) as f64;
+    if x.fract() != 0.0 || !x.is_finite() {
+        None
+    } else if x >= 0.0 {
+        Some(x)
+            .filter(|&x| x < 2.0 * TWO_POW_127)
+            .map(|x| Repr::NonNeg(x as u128))
+    } else {
+        Some(x)
+            .filter(|&x| x >= -TWO_POW_127)
+            .map(|x| Repr::Neg(x as i128))
+    }
+}
+
+/// The low 128 bits of the two's complement of `x`, an integral float.
+/// Infinities give 0.
+fn wrap_float(x: f64) -> u128 {
+    if !x.is_finite() || x == 0.0 {
+        return 0;
+    }
+    let bits = x.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i32;
+    let mantissa = (bits & ((1 << 52) - 1)) | (1 << 52);
+    let shift = exponent - 1075;
+    let magnitude = if shift < 0 {
+        (mantissa >> -shift) as u128
+    } else if shift < 128 {
+        (mantissa as u128) << shift
+    } else {
+        0
+    };
+    if x < 0.0 {
+        magnitude.wrapping_neg()
+    } else {
+        magnitude
+    }
+}
+
+/// What `as` does to a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Effect {
+    Exact,
+    /// A float was truncated toward zero.
+    FractionDropped,
+    /// Precision was lost converting to a float.
+    Rounded,
+    /// An integer kept only its low bits.
+    Wrapped,
+    /// A float was clamped to a bound of the integer type.
+    Saturated,
+    NanToZero,
+    /// The value is too large for the float type.
+    Infinite,
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            Effect::Exact => "exact",
+            Effect::FractionDropped => "fractional part dropped",
+            Effect::Rounded => "rounded to the nearest representable value",
+            Effect::Wrapped => "wrapped around, keeping the low bits",
+            Effect::Saturated => "saturated at the bound of the target type",
+            Effect::NanToZero => "NaN became 0",
+            Effect::Infinite => "overflowed to infinity",
+        };
+        f.write_str(description)
+    }
+}
+
+/// The outcome of casting one value under each policy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub value: String,
+    pub from: &'static str,
+    pub to: &'static str,
+    /// The result of `value as to`.
+    pub as_cast: String,
+    pub effect: Effect,
+    pub checked: Result<String, CastError>,
+    pub saturating: String,
+    pub wrapping: String,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{} as {} = {} ({})",
+            self.value, self.from, self.to, self.as_cast, self.effect
+        )
+    }
+}
+
+/// Explains what `value as U` does.
+///
+///     use types::cast::{self, Effect};
+///
+///     let report = cast::report::<f32, u8>(300.0);
+///     assert_eq!(report.effect, Effect::Saturated);
+///     assert_eq!(
+///         report.to_string(),
+///         "300f32 as u8 = 255 (saturated at the bound of the target type)"
+///     );
+pub fn report<T: Primitive, U: Primitive>(value: T) -> Report {
+    let to_float = matches!(U::KIND, Kind::Float { .. });
+    let checked = checked::<T, U>(value);
+    // `as` truncates like `TowardZero`, so anything else it does is caught as
+    // an error here
+    let truncated = cast::<T, U>(value, Policy::new(Overflow::Error, Rounding::TowardZero));
+    let effect = match (&checked, truncated) {
+        (Ok(_), _) => Effect::Exact,
+        (Err(_), Ok(_)) if to_float => Effect::Rounded,
+        (Err(_), Ok(_)) => Effect::FractionDropped,
+        (Err(_), Err(CastError::NotANumber { .. })) => Effect::NanToZero,
+        (Err(_), Err(_)) if to_float => Effect::Infinite,
+        (Err(_), Err(_)) => match T::KIND {
+            Kind::Float { .. } => Effect::Saturated,
+            Kind::Int { .. } => Effect::Wrapped,
+        },
+    };
+    Report {
+        value: value.to_string(),
+        from: T::NAME,
+        to: U::NAME,
+        as_cast: as_cast::<T, U>(value).to_string(),
+        effect,
+        checked: checked.map(|v| v.to_string()),
+        saturating: saturating::<T, U>(value).to_string(),
+        wrapping: wrapping::<T, U>(value).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapping_and_saturating_match_as() {
+        assert_eq!(wrapping::<i32, u8>(1000), 232);
+        assert_eq!(wrapping::<i8, u8>(-1), 255);
+        assert_eq!(wrapping::<i32, i8>(232), -24);
+        assert_eq!(wrapping::<i128, u128>(-1), u128::MAX);
+        assert_eq!(saturating::<i32, u8>(1000), 255);
+        assert_eq!(saturating::<i32, u8>(-1000), 0);
+        assert_eq!(saturating::<u128, i8>(u128::MAX), 127);
+        for x in [
+            300.0f32,
+            -100.0,
+            65.4321,
+            f32::NAN,
+            f32::INFINITY,
+            -0.5,
+            1e30,
+        ] {
+            assert_eq!(as_cast::<f32, u8>(x), x as u8);
+            assert_eq!(as_cast::<f32, i64>(x), x as i64);
+            assert_eq!(as_cast::<f32, u128>(x), x as u128);
+        }
+        assert_eq!(as_cast::<u128, f32>(u128::MAX), u128::MAX as f32);
+        assert_eq!(as_cast::<f64, f32>(1e300), f32::INFINITY);
+    }
+
+    #[test]
+    fn wraps_floats_like_integers() {
+        assert_eq!(wrapping::<f32, u8>(300.7), 44);
+        assert_eq!(wrapping::<f64, i8>(-129.0), 127);
+        assert_eq!(wrapping::<f64, u64>(2f64.powi(64) + 4096.0), 4096);
+        assert_eq!(wrapping::<f64, u8>(2f64.powi(200)), 0);
+        assert_eq!(wrapping::<f64, i32>(-(2f64.powi(140) + 2f64.powi(90))), 0);
+        assert_eq!(
+            wrapping::<f64, u128>(-(2f64.powi(140) + 2f64.powi(90))),
+            (1u128 << 90).wrapping_neg()
+        );
+        assert_eq!(wrapping::<f64, i16>(f64::NEG_INFINITY), 0);
+        assert_eq!(wrapping::<f32, u8>(f32::NAN), 0);
+    }
+
+    #[test]
+    fn checked_rejects_any_change() {
+        assert_eq!(checked::<i32, u8>(255), Ok(255u8));
+        assert_eq!(
+            checked::<i32, u8>(1000),
+            Err(CastError::OutOfRange {
+                value: "1000".to_string(),
+                to: "u8"
+            })
+        );
+        assert!(checked::<i8, u64>(-1).is_err());
+        assert_eq!(
+            checked::<f32, u8>(f32::NAN),
+            Err(CastError::NotANumber { to: "u8" })
+        );
+        assert!(checked::<f64, i32>(0.5).is_err());
+        assert_eq!(checked::<f64, i32>(-7.0), Ok(-7));
+        assert_eq!(checked::<u64, f64>(1 << 53), Ok(9007199254740992.0));
+        assert!(checked::<u64, f64>((1 << 53) + 1).is_err());
+        assert_eq!(checked::<i128, f32>(i128::MIN), Ok(-(2f32.powi(127))));
+        assert!(checked::<f64, f32>(0.1).is_err());
+        assert_eq!(checked::<f64, f32>(0.5), Ok(0.5));
+        assert!(checked::<f64, f32>(f64::NAN).unwrap().is_nan());
+        assert!(checked::<u128, f32>(u128::MAX).is_err());
+        assert_eq!(checked::<f64, i128>(-(2f64.powi(127))), Ok(i128::MIN));
+        assert!(checked::<f64, u128>(2f64.powi(128)).is_err());
+    }
+
+    #[test]
+    fn infinities_follow_the_overflow_policy() {
+        for (x, saturated) in [(f32::INFINITY, u8::MAX), (f32::NEG_INFINITY, 0)] {
+            let policy = |overflow| Policy::new(overflow, Rounding::Exact);
+            assert_eq!(
+                cast::<f32, u8>(x, policy(Overflow::Error)),
+                Err(CastError::OutOfRange {
+                    value: x.to_string(),
+                    to: "u8"
+                })
+            );
+            assert_eq!(
+                cast::<f32, u8>(x, policy(Overflow::Saturate)),
+                Ok(saturated)
+            );
+            assert_eq!(cast::<f32, u8>(x, policy(Overflow::Wrap)), Ok(0));
+        }
+        assert_eq!(
+            checked::<f64, i64>(f64::NEG_INFINITY),
+            Err(CastError::OutOfRange {
+                value: "-inf".to_string(),
+                to: "i64"
+            })
+        );
+    }
+
+    #[test]
+    fn rounds_floats_to_integers() {
+        let round = |x: f64, rounding| cast::<f64, i32>(x, Policy::CHECKED.rounding(rounding));
+        assert_eq!(round(2.5, Rounding::TowardZero), Ok(2));
+        assert_eq!(round(2.5, Rounding::HalfAwayFromZero), Ok(3));
+        assert_eq!(round(2.5, Rounding::HalfEven), Ok(2));
+        assert_eq!(round(-2.5, Rounding::HalfAwayFromZero), Ok(-3));
+        assert_eq!(round(-2.5, Rounding::Floor), Ok(-3));
+        assert_eq!(round(-2.5, Rounding::Ceiling), Ok(-2));
+        assert!(round(2.5, Rounding::Exact).is_err());
+        // rounding happens before the range check
+        let policy = Policy::CHECKED.rounding(Rounding::HalfAwayFromZero);
+        assert_eq!(cast::<f32, u8>(255.4, policy), Ok(255));
+        assert!(cast::<f32, u8>(255.5, policy).is_err());
+        assert_eq!(cast::<f32, u8>(-0.4, policy), Ok(0));
+    }
+
+    #[test]
+    fn saturates_float_targets() {
+        assert_eq!(saturating::<f64, f32>(1e300), f32::MAX);
+        assert_eq!(saturating::<f64, f32>(-1e300), f32::MIN);
+        assert_eq!(saturating::<u128, f32>(u128::MAX), f32::MAX);
+        assert_eq!(saturating::<f64, f32>(f64::INFINITY), f32::INFINITY);
+    }
+
+    #[test]
+    fn reports_what_as_does() {
+        let effect = |report: Report| report.effect;
+        assert_eq!(effect(report::<u8, i32>(200)), Effect::Exact);
+        assert_eq!(effect(report::<f32, u8>(65.4321)), Effect::FractionDropped);
+        assert_eq!(effect(report::<i32, u8>(1000)), Effect::Wrapped);
+        assert_eq!(effect(report::<f32, u8>(-100.0)), Effect::Saturated);
+        assert_eq!(effect(report::<f32, u8>(f32::NAN)), Effect::NanToZero);
+        assert_eq!(effect(report::<u64, f32>(u64::MAX - 1)), Effect::Rounded);
+        assert_eq!(effect(report::<f64, f32>(1e300)), Effect::Infinite);
+        let report = report::<i32, u8>(1000);
+        assert_eq!(report.as_cast, "232");
+        assert_eq!(report.saturating, "255");
+        assert_eq!(report.wrapping, "232");
+        assert!(report.checked.is_err());
+        assert_eq!(
+            report.to_string(),
+            "1000i32 as u8 = 232 (wrapped around, keeping the low bits)"
+        );
+    }
+}
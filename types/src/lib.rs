@@ -0,0 +1,6 @@
+// Types library
+//
+// Reusable versions of the examples in `main.rs`.
+
+pub mod cast;
+pub mod units;
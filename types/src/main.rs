@@ -42,11 +42,18 @@ fn main() {
     println!("-100.0 as u8 is : {}", -100.0f32 as u8);
     println!("   non as u8 is : {}", f32::NAN as u8);
 
-    unsafe {
-        println!(" 300.0 as u8 is : {}", 300.0f32.to_int_unchecked::<u8>());
-        println!("-100.0 as u8 is : {}", (-100.0f32).to_int_unchecked::<u8>());
-        println!("   non as u8 is : {}", f32::NAN.to_int_unchecked::<u8>());
-    }
+    // `to_int_unchecked` skips that check, and calling it on an out-of-range
+    // value is undefined behaviour. To choose what happens instead, use an
+    // explicit policy from `types::cast`; `report` explains what `as` did.
+    use types::cast::{self, Policy, Rounding};
+
+    println!(" 300.0 checked is : {:?}", cast::checked::<f32, u8>(300.0));
+    println!(" 300.7 wrapped is : {}", cast::wrapping::<f32, u8>(300.7));
+    let nearest = Policy::CHECKED.rounding(Rounding::HalfEven);
+    println!("  65.5 rounded is : {:?}", cast::cast::<f32, u8>(65.5, nearest));
+    println!("{}", cast::report::<f32, u8>(-100.0));
+    println!("{}", cast::report::<f32, u8>(f32::NAN));
+    println!("{}", cast::report::<i32, u8>(1000));
 }
 */
 
@@ -105,20 +112,29 @@ fn main() {
 // Types must have `UpperCamelCase` names, or the compiler will raise a warning.
 // The exception to this rule are the primitive types: `usize`, `f32`, etc./* */
 /* */
-type NanoSecond = u64;
-type Inch = u64;
 type U64 = u64;
 
+// A new type, on the other hand, is a distinct type. `types::units` has one
+// for each unit of time and length.
+use types::units::{Inches, Micrometres, Millis, Nanos, Unit};
+
 fn main() {
-    let nanoseconds: NanoSecond = 5 as U64;
-    let inches: Inch = 2 as U64;
+    let count: U64 = 5;
+    let nanoseconds = Nanos(count);
+    let inches = Inches(2);
 
     // Note that type aliases *don't* provide any extra type safety, because
-    // aliases are *not* new types
+    // aliases are *not* new types: `count + 2` is just a `u64`.
+    println!("{} + 2 = {}", count, count + 2);
+
+    // println!("{}", nanoseconds + inches); // ERROR.. mismatched types
+    // println!("{}", nanoseconds + Millis(1)); // ERROR.. mismatched types
+    let millis = Millis(1).convert::<Nanos>().unwrap();
+    println!("{} + {} = {}", nanoseconds, millis, nanoseconds + millis);
+    println!("{} is {:?}", inches, inches.convert::<Micrometres>());
     println!(
-        "{} nanoseconds + {} inches = {} unit?",
+        "{} in whole milliseconds is {:?}",
         nanoseconds,
-        inches,
-        nanoseconds + inches
+        nanoseconds.truncate::<Millis>()
     );
 }
@@ -0,0 +1,340 @@
+// Units
+//
+// New types for durations and lengths. Unlike `type NanoSecond = u64`, each
+// unit is its own type, so `Nanos(5) + Inches(2)` and even `Nanos(5) +
+// Millis(2)` are compile errors. Converting between units of the same
+// dimension is explicit: `convert` fails rather than lose precision or
+// overflow, `truncate` rounds toward zero and only fails on overflow.
+//
+//     use types::units::{Inches, Micrometres, Millimetres, Millis, Nanos, Unit};
+//
+//     assert_eq!(Millis(3).convert::<Nanos>(), Ok(Nanos(3_000_000)));
+//     assert!(Nanos(1_500_000).convert::<Millis>().is_err());
+//     assert_eq!(Nanos(1_500_000).truncate::<Millis>(), Ok(Millis(1)));
+//     assert_eq!(Inches(1).convert::<Micrometres>(), Ok(Micrometres(25_400)));
+//     assert_eq!(Inches(5).to_string(), "5in");
+//     assert_eq!(Inches(5).convert::<Millimetres>(), Ok(Millimetres(127)));
+
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, Div, Mul, Sub};
+use std::time::Duration;
+
+/// The dimension of the duration units. Base unit: one nanosecond.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Time {}
+
+/// The dimension of the length units. Base unit: one micrometre.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Length {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnitError {
+    /// The value doesn't fit in a `u64` of the target unit.
+    Overflow { value: String, to: &'static str },
+    /// The value isn't a whole number of the target unit.
+    Inexact { value: String, to: &'static str },
+}
+
+impl fmt::Display for UnitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UnitError::Overflow { value, to } => write!(f, "{} overflows {}", value, to),
+            UnitError::Inexact { value, to } => {
+                write!(f, "{} is not a whole number of {}", value, to)
+            }
+        }
+    }
+}
+
+impl error::Error for UnitError {}
+
+pub trait Unit: Copy + fmt::Display {
+    type Dimension;
+    /// The size of one unit in the base unit of its dimension.
+    const SCALE: u64;
+    const SUFFIX: &'static str;
+
+    fn from_count(count: u64) -> Self;
+    fn count(self) -> u64;
+
+    /// The same quantity in unit `V`, failing unless it is exact and fits.
+    fn convert<V: Unit<Dimension = Self::Dimension>>(self) -> Result<V, UnitError> {
+        let base = u128::from(self.count()) * u128::from(Self::SCALE);
+        if base % u128::from(V::SCALE) != 0 {
+            return Err(UnitError::Inexact {
+                value: self.to_string(),
+                to: V::SUFFIX,
+            });
+        }
+        self.truncate()
+    }
+
+    /// The whole number of `V` in this quantity, failing only if it
+    /// doesn't fit.
+    fn truncate<V: Unit<Dimension = Self::Dimension>>(self) -> Result<V, UnitError> {
+        let base = u128::from(self.count()) * u128::from(Self::SCALE);
+        u64::try_from(base / u128::from(V::SCALE))
+            .map(V::from_count)
+            .map_err(|_| UnitError::Overflow {
+                value: self.to_string(),
+                to: V::SUFFIX,
+            })
+    }
+}
+
+macro_rules! units {
+    ($dimension:ty: $($unit:ident = $scale:expr, $suffix:expr;)*) => {
+        $(
+            #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+            pub struct $unit(pub u64);
+
+            impl Unit for $unit {
+                type Dimension = $dimension;
+                const SCALE: u64 = $scale;
+                const SUFFIX: &'static str = $suffix;
+
+                fn from_count(count: u64) -> Self {
+                    $unit(count)
+                }
+
+                fn count(self) -> u64 {
+                    self.0
+                }
+            }
+
+            impl $unit {
+                pub fn checked_add(self, rhs: $unit) -> Option<$unit> {
+                    self.0.checked_add(rhs.0).map($unit)
+                }
+
+                pub fn checked_sub(self, rhs: $unit) -> Option<$unit> {
+                    self.0.checked_sub(rhs.0).map($unit)
+                }
+
+                pub fn checked_mul(self, rhs: u64) -> Option<$unit> {
+                    self.0.checked_mul(rhs).map($unit)
+                }
+            }
+
+            impl fmt::Display for $unit {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    write!(f, "{}{}", self.0, $suffix)
+                }
+            }
+
+            /// # Panics
+            ///
+            /// Panics if the sum doesn't fit in a `u64`.
+            impl Add for $unit {
+                type Output = $unit;
+
+                fn add(self, rhs: $unit) -> $unit {
+                    self.checked_add(rhs).expect("unit overflow")
+                }
+            }
+
+            /// # Panics
+            ///
+            /// Panics if `rhs` is larger than `self`.
+            impl Sub for $unit {
+                type Output = $unit;
+
+                fn sub(self, rhs: $unit) -> $unit {
+                    self.checked_sub(rhs).expect("unit underflow")
+                }
+            }
+
+            /// # Panics
+            ///
+            /// Panics if the product doesn't fit in a `u64`.
+            impl Mul<u64> for $unit {
+                type Output = $unit;
+
+                fn mul(self, rhs: u64) -> $unit {
+                    self.checked_mul(rhs).expect("unit overflow")
+                }
+            }
+
+            impl Div<u64> for $unit {
+                type Output = $unit;
+
+                fn div(self, rhs: u64) -> $unit {
+                    $unit(self.0 / rhs)
+                }
+            }
+
+            impl Sum for $unit {
+                fn sum<I: Iterator<Item = $unit>>(iter: I) -> $unit {
+                    iter.fold($unit(0), |total, unit| {
+                        total.checked_add(unit).expect("unit overflow")
+                    })
+                }
+            }
+        )*
+        conversions!($($unit),*);
+    };
+}
+
+// `TryFrom` between every pair of units, in both directions.
+macro_rules! conversions {
+    ($first:ident $(, $rest:ident)*) => {
+        $(
+            impl TryFrom<$first> for $rest {
+                type Error = UnitError;
+
+                fn try_from(value: $first) -> Result<$rest, UnitError> {
+                    value.convert()
+                }
+            }
+
+            impl TryFrom<$rest> for $first {
+                type Error = UnitError;
+
+                fn try_from(value: $rest) -> Result<$first, UnitError> {
+                    value.convert()
+                }
+            }
+        )*
+        conversions!($($rest),*);
+    };
+    () => {};
+}
+
+units! {
+    Time:
+    Nanos = 1, "ns";
+    Micros = 1_000, "µs";
+    Millis = 1_000_000, "ms";
+    Seconds = 1_000_000_000, "s";
+}
+
+units! {
+    Length:
+    Micrometres = 1, "µm";
+    Millimetres = 1_000, "mm";
+    Metres = 1_000_000, "m";
+    Inches = 25_400, "in";
+}
+
+macro_rules! duration {
+    ($($unit:ident: $from:ident;)*) => {$(
+        impl From<$unit> for Duration {
+            fn from(value: $unit) -> Duration {
+                Duration::$from(value.0)
+            }
+        }
+
+        /// Fails if the duration isn't a whole number of units, or if there
+        /// are too many of them for a `u64`.
+        impl TryFrom<Duration> for $unit {
+            type Error = UnitError;
+
+            fn try_from(value: Duration) -> Result<$unit, UnitError> {
+                let nanos = value.as_nanos();
+                let to = <$unit as Unit>::SUFFIX;
+                let scale = u128::from(<$unit as Unit>::SCALE);
+                if nanos % scale != 0 {
+                    return Err(UnitError::Inexact {
+                        value: format!("{:?}", value),
+                        to,
+                    });
+                }
+                u64::try_from(nanos / scale)
+                    .map($unit)
+                    .map_err(|_| UnitError::Overflow {
+                        value: format!("{:?}", value),
+                        to,
+                    })
+            }
+        }
+    )*};
+}
+
+duration! {
+    Nanos: from_nanos;
+    Micros: from_micros;
+    Millis: from_millis;
+    Seconds: from_secs;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_exactly_or_fails() {
+        assert_eq!(Seconds::try_from(Millis(3_000)), Ok(Seconds(3)));
+        assert_eq!(
+            Seconds::try_from(Millis(3_001)),
+            Err(UnitError::Inexact {
+                value: "3001ms".to_string(),
+                to: "s"
+            })
+        );
+        assert_eq!(Millis(3_001).truncate::<Seconds>(), Ok(Seconds(3)));
+        assert_eq!(Nanos::try_from(Seconds(18)), Ok(Nanos(18_000_000_000)));
+        assert_eq!(
+            Nanos::try_from(Seconds(u64::MAX / 1_000)),
+            Err(UnitError::Overflow {
+                value: format!("{}s", u64::MAX / 1_000),
+                to: "ns"
+            })
+        );
+        assert_eq!(Micrometres::try_from(Metres(2)), Ok(Micrometres(2_000_000)));
+        assert!(Millimetres::try_from(Inches(1)).is_err());
+        assert_eq!(Millimetres::try_from(Inches(10)), Ok(Millimetres(254)));
+        assert_eq!(Inches(1).truncate::<Millimetres>(), Ok(Millimetres(25)));
+    }
+
+    #[test]
+    fn interoperates_with_duration() {
+        assert_eq!(Duration::from(Millis(1_500)), Duration::from_millis(1_500));
+        assert_eq!(
+            Micros::try_from(Duration::from_millis(2)),
+            Ok(Micros(2_000))
+        );
+        assert!(Seconds::try_from(Duration::from_millis(2)).is_err());
+        assert!(matches!(
+            Seconds::try_from(Duration::MAX),
+            Err(UnitError::Inexact { .. })
+        ));
+        assert!(matches!(
+            Nanos::try_from(Duration::from_secs(u64::MAX)),
+            Err(UnitError::Overflow { .. })
+        ));
+    }
+
+    #[test]
+    fn arithmetic_stays_within_a_unit() {
+        let total: Millis = [Millis(1), Millis(2), Millis(3)].into_iter().sum();
+        assert_eq!(total, Millis(6));
+        assert_eq!(total * 2 - Millis(2), Millis(10));
+        assert_eq!(total / 4, Millis(1));
+        assert!(Millis(1) < Millis(2));
+        assert_eq!(Nanos(u64::MAX).checked_add(Nanos(1)), None);
+        assert_eq!(Nanos(0).checked_sub(Nanos(1)), None);
+        assert_eq!(Metres(3).to_string(), "3m");
+        assert_eq!(Micros(7).to_string(), "7µs");
+    }
+
+    #[test]
+    #[should_panic(expected = "unit underflow")]
+    fn sub_panics_below_zero() {
+        let _ = Millis(1) - Millis(2);
+    }
+
+    #[test]
+    #[should_panic(expected = "unit overflow")]
+    fn sum_panics_on_overflow() {
+        let _: Nanos = [Nanos(u64::MAX), Nanos(1)].into_iter().sum();
+    }
+
+    #[test]
+    #[should_panic(expected = "unit overflow")]
+    fn mul_panics_on_overflow() {
+        let _ = Seconds(u64::MAX / 2 + 1) * 2;
+    }
+}